@@ -0,0 +1,67 @@
+use super::track::Track;
+use crate::perspective_camera::camera::Camera;
+use crate::vector::{Point3, Vec3};
+
+/// Keyframed camera parameters. Tracks without keys leave the camera's own setting alone.
+pub struct CameraAnimation {
+    pub camera_origin: Track<Point3>,
+    pub camera_target: Track<Point3>,
+    pub vfov: Track<f64>,
+    pub focus_distance: Track<f64>,
+}
+
+impl CameraAnimation {
+    /// Creates an animation with no keys.
+    pub fn new() -> Self {
+        CameraAnimation {
+            camera_origin: Track::new(),
+            camera_target: Track::new(),
+            vfov: Track::new(),
+            focus_distance: Track::new(),
+        }
+    }
+
+    /// Sets the camera's animated parameters to their values at `time`.
+    pub fn apply(&self, camera: &mut Camera, time: f64) {
+        if let Some(camera_origin) = self.camera_origin.at(time) {
+            camera.camera_origin = camera_origin;
+        }
+        if let Some(camera_target) = self.camera_target.at(time) {
+            camera.camera_target = camera_target;
+        }
+        if let Some(vfov) = self.vfov.at(time) {
+            camera.vfov = vfov;
+        }
+        if let Some(focus_distance) = self.focus_distance.at(time) {
+            camera.focus_distance = focus_distance;
+        }
+    }
+
+    /// Returns where the camera is and what it looks at at `time`, falling back to the given
+    /// values for tracks without keys.
+    pub fn view_at(
+        &self,
+        time: f64,
+        camera_origin: Point3,
+        camera_target: Point3,
+    ) -> (Point3, Point3) {
+        (
+            self.camera_origin.at(time).unwrap_or(camera_origin),
+            self.camera_target.at(time).unwrap_or(camera_target),
+        )
+    }
+
+    /// Indicates whether the camera moves, which is what causes camera motion blur.
+    pub fn is_moving(&self) -> bool {
+        self.camera_origin.keys().len() > 1 || self.camera_target.keys().len() > 1
+    }
+}
+
+/// Returns the camera basis `(u, v, w)` for a camera at `origin` looking at `target`, as
+/// `Camera::initialize` sets it up.
+pub fn view_basis(origin: Point3, target: Point3, up_vector: Vec3) -> (Vec3, Vec3, Vec3) {
+    let w = (origin - target).normalize();
+    let u = Vec3::cross(&up_vector, &w).normalize();
+    let v = Vec3::cross(&w, &u);
+    (u, v, w)
+}
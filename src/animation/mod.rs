@@ -0,0 +1,3 @@
+pub mod camera_animation;
+pub mod track;
+pub mod transform;
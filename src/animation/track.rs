@@ -0,0 +1,169 @@
+use std::ops::{Add, Mul, Sub};
+
+/// Values that can be keyframed: anything that can be blended by weighted sums, such as `f64`
+/// and `Vec3`.
+pub trait Animatable:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<f64, Output = Self>
+{
+}
+
+impl<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f64, Output = T>> Animatable for T {}
+
+/// How a track moves from one keyframe to the next.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+    /// Straight-line interpolation at constant speed.
+    Linear,
+    /// A smooth cubic Bezier curve whose handles follow the neighboring keys, easing in and out
+    /// at the first and last keys.
+    Bezier,
+}
+
+/// A value at a point in time.
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe<T> {
+    pub time: f64,
+    pub value: T,
+    /// How to interpolate from this key to the next one.
+    pub interpolation: Interpolation,
+}
+
+/// A value that changes over time, given by keyframes.
+///
+/// Before the first key and after the last the value holds still.
+#[derive(Clone, Debug)]
+pub struct Track<T> {
+    keys: Vec<Keyframe<T>>,
+}
+
+impl<T: Animatable> Track<T> {
+    /// Creates a track without keys.
+    pub fn new() -> Self {
+        Track { keys: Vec::new() }
+    }
+
+    /// Adds a key, keeping the keys in time order. A key at the same time as an existing one
+    /// replaces it.
+    pub fn add_key(&mut self, time: f64, value: T, interpolation: Interpolation) {
+        let key = Keyframe {
+            time,
+            value,
+            interpolation,
+        };
+        let index = self.keys.partition_point(|existing| existing.time < time);
+        match self.keys.get(index) {
+            Some(existing) if existing.time == time => self.keys[index] = key,
+            _ => self.keys.insert(index, key),
+        }
+    }
+
+    /// Returns the keys in time order.
+    pub fn keys(&self) -> &[Keyframe<T>] {
+        &self.keys
+    }
+
+    /// Indicates whether the track has no keys.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the value at `time`, or `None` if the track has no keys.
+    pub fn at(&self, time: f64) -> Option<T> {
+        let first = self.keys.first()?;
+        let last = self.keys.last()?;
+        if time <= first.time {
+            return Some(first.value);
+        }
+        if time >= last.time {
+            return Some(last.value);
+        }
+
+        let next = self.keys.partition_point(|key| key.time <= time);
+        let (start, end) = (&self.keys[next - 1], &self.keys[next]);
+        let duration = end.time - start.time;
+        let t = (time - start.time) / duration;
+        match start.interpolation {
+            Interpolation::Linear => Some(start.value + (end.value - start.value) * t),
+            Interpolation::Bezier => {
+                let handle_out = start.value + self.slope(next - 1) * (duration / 3.0);
+                let handle_in = end.value - self.slope(next) * (duration / 3.0);
+                Some(bezier(start.value, handle_out, handle_in, end.value, t))
+            }
+        }
+    }
+
+    /// Returns the rate of change at key `index` used to place Bezier handles: the slope
+    /// between its neighbors, as in a Catmull–Rom spline, and zero at the ends.
+    fn slope(&self, index: usize) -> T {
+        let key = &self.keys[index];
+        if index == 0 || index + 1 == self.keys.len() {
+            return key.value * 0.0;
+        }
+        let (previous, next) = (&self.keys[index - 1], &self.keys[index + 1]);
+        (next.value - previous.value) * (1.0 / (next.time - previous.time))
+    }
+}
+
+/// Evaluates a cubic Bezier curve with de Casteljau's algorithm.
+fn bezier<T: Animatable>(p0: T, p1: T, p2: T, p3: T, t: f64) -> T {
+    let lerp = |a: T, b: T| a + (b - a) * t;
+    let (q0, q1, q2) = (lerp(p0, p1), lerp(p1, p2), lerp(p2, p3));
+    let (r0, r1) = (lerp(q0, q1), lerp(q1, q2));
+    lerp(r0, r1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(keys: &[(f64, f64)], interpolation: Interpolation) -> Track<f64> {
+        let mut track = Track::new();
+        for &(time, value) in keys {
+            track.add_key(time, value, interpolation);
+        }
+        track
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap();
+        assert!((actual - expected).abs() < 1e-9, "{actual} vs {expected}");
+    }
+
+    #[test]
+    fn empty_track_has_no_value() {
+        assert_eq!(Track::<f64>::new().at(1.0), None);
+    }
+
+    #[test]
+    fn linear_keys() {
+        let track = track(&[(1.0, 10.0), (3.0, 20.0)], Interpolation::Linear);
+        assert_close(track.at(2.0), 15.0);
+        assert_close(track.at(2.5), 17.5);
+        // The value holds still outside the keys.
+        assert_close(track.at(0.0), 10.0);
+        assert_close(track.at(4.0), 20.0);
+    }
+
+    #[test]
+    fn bezier_keys_ease_and_stay_smooth() {
+        let track = track(&[(0.0, 0.0), (1.0, 1.0), (3.0, 3.0)], Interpolation::Bezier);
+        assert_close(track.at(1.0), 1.0);
+        // Easing out of the first key, the curve lags behind a straight line.
+        assert_close(track.at(0.5), 0.375);
+        // Through the middle key the slope is that between its neighbors on both sides.
+        let slope =
+            |from: f64, to: f64| (track.at(to).unwrap() - track.at(from).unwrap()) / (to - from);
+        assert!((slope(1.0 - 1e-6, 1.0) - 1.0).abs() < 1e-4);
+        assert!((slope(1.0, 1.0 + 1e-6) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn keys_are_sorted_and_replaced() {
+        let mut track = track(&[(2.0, 20.0), (0.0, 0.0)], Interpolation::Linear);
+        track.add_key(2.0, 40.0, Interpolation::Linear);
+        let times: Vec<f64> = track.keys().iter().map(|key| key.time).collect();
+        assert_eq!(times, [0.0, 2.0]);
+        assert_close(track.at(1.0), 20.0);
+        assert_close(track.at(2.0), 40.0);
+    }
+}
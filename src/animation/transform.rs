@@ -0,0 +1,110 @@
+use super::track::Track;
+use crate::math::interval::Interval;
+use crate::math::rt_math::degrees_to_radians;
+use crate::traceable::{HitRecord, Traceable};
+use crate::vector::{dot, Point3, Vec3};
+use crate::Ray;
+use std::sync::Arc;
+
+/// Moves, rotates and scales an object over time with keyframed tracks.
+///
+/// The object is scaled uniformly about its own origin, rotated by the Euler angles in degrees
+/// (about X, then Y, then Z), and then translated. Tracks without keys leave the object as is.
+/// Each ray sees the object as it is at the ray's time, so objects blur as they move.
+pub struct AnimatedTransform {
+    object: Arc<dyn Traceable>,
+    pub translation: Track<Vec3>,
+    pub rotation: Track<Vec3>,
+    pub scale: Track<f64>,
+}
+
+impl AnimatedTransform {
+    /// Wraps `object` with empty tracks, leaving it in place until keys are added.
+    pub fn from(object: Arc<dyn Traceable>) -> Self {
+        AnimatedTransform {
+            object,
+            translation: Track::new(),
+            rotation: Track::new(),
+            scale: Track::new(),
+        }
+    }
+}
+
+impl Traceable for AnimatedTransform {
+    /// Moves the ray into the object's own space at the ray's time, intersects it there and
+    /// moves the hit back out.
+    ///
+    /// With a uniform scale the ray parameter is the same in both spaces, so the interval and
+    /// the hit's parameter carry over unchanged.
+    fn hit(&self, ray: &Ray, ray_parameter: Interval, record: &mut HitRecord) -> bool {
+        let time = ray.time();
+        let translation = self.translation.at(time).unwrap_or(Vec3::new());
+        let rotation = Rotation::from_euler_degrees(self.rotation.at(time).unwrap_or(Vec3::new()));
+        let scale = self.scale.at(time).unwrap_or(1.0);
+
+        let mut object_ray = Ray::from(
+            rotation.inverse_apply(&(ray.origin() - translation)) / scale,
+            rotation.inverse_apply(&ray.direction()) / scale,
+            time,
+        );
+        object_ray.set_wavelengths(ray.wavelengths());
+        if !self.object.hit(&object_ray, ray_parameter, record) {
+            return false;
+        }
+
+        record.set_point(translation + scale * rotation.apply(&record.point()));
+        let normal = rotation.apply(&record.normal());
+        let outward_normal = rotation.apply(&record.geometric_normal());
+        let outward_normal = match record.ray_faces_outside() {
+            true => outward_normal,
+            _ => -outward_normal,
+        };
+        record.set_normal_face(ray, &outward_normal);
+        record.set_normal(normal);
+        let (dpdu, dpdv) = (record.dpdu(), record.dpdv());
+        record.set_tangents(scale * rotation.apply(&dpdu), scale * rotation.apply(&dpdv));
+        true
+    }
+}
+
+/// A rotation stored as the rows of its matrix.
+struct Rotation {
+    rows: [Vec3; 3],
+}
+
+impl Rotation {
+    /// Builds the rotation about X, then Y, then Z by the angles in `degrees`.
+    fn from_euler_degrees(degrees: Vec3) -> Self {
+        let (sin_x, cos_x) = degrees_to_radians(degrees.x()).sin_cos();
+        let (sin_y, cos_y) = degrees_to_radians(degrees.y()).sin_cos();
+        let (sin_z, cos_z) = degrees_to_radians(degrees.z()).sin_cos();
+        Rotation {
+            rows: [
+                Vec3::from(
+                    cos_y * cos_z,
+                    sin_x * sin_y * cos_z - cos_x * sin_z,
+                    cos_x * sin_y * cos_z + sin_x * sin_z,
+                ),
+                Vec3::from(
+                    cos_y * sin_z,
+                    sin_x * sin_y * sin_z + cos_x * cos_z,
+                    cos_x * sin_y * sin_z - sin_x * cos_z,
+                ),
+                Vec3::from(-sin_y, sin_x * cos_y, cos_x * cos_y),
+            ],
+        }
+    }
+
+    fn apply(&self, vector: &Vec3) -> Vec3 {
+        Vec3::from(
+            dot(&self.rows[0], vector),
+            dot(&self.rows[1], vector),
+            dot(&self.rows[2], vector),
+        )
+    }
+
+    /// Applies the inverse rotation, which for a rotation matrix is its transpose.
+    fn inverse_apply(&self, vector: &Vec3) -> Vec3 {
+        vector.x() * self.rows[0] + vector.y() * self.rows[1] + vector.z() * self.rows[2]
+    }
+}
@@ -1,6 +1,7 @@
 use crate::material::Material;
 use crate::materials::{self, *};
 use crate::math::interval::Interval;
+use crate::math::rt_math::{random_number, PI};
 use crate::ray::Ray;
 use crate::traceable::{HitRecord, Traceable};
 use crate::vector::{dot, Point3, Vec3};
@@ -14,6 +15,8 @@ pub struct Sphere {
     material: Arc<dyn Material>,
     in_motion: bool,
     motion_blur_lerp_factor: Point3,
+    /// Time at which the sphere is at `center`.
+    motion_start_time: f64,
 }
 
 impl Sphere {
@@ -24,6 +27,7 @@ impl Sphere {
             material: Arc::from(Lambertian::new()),
             in_motion: false,
             motion_blur_lerp_factor: Point3::new(),
+            motion_start_time: 0.0,
         }
     }
 
@@ -33,13 +37,31 @@ impl Sphere {
         radius: f64,
         material: Arc<dyn Material>,
     ) -> Sphere {
-        return Sphere {
+        Sphere::moving_between(center_start, 0.0, center_end, 1.0, radius, material)
+    }
+
+    /// Creates a sphere moving in a straight line from `center_start` at `time_start` to
+    /// `center_end` at `time_end`, continuing at the same speed outside that interval.
+    ///
+    /// Times are in the same units as the camera's shutter, usually seconds. Panics unless
+    /// `time_end` comes after `time_start`; a sphere that does not move is made with `from`.
+    pub fn moving_between(
+        center_start: Point3,
+        time_start: f64,
+        center_end: Point3,
+        time_end: f64,
+        radius: f64,
+        material: Arc<dyn Material>,
+    ) -> Sphere {
+        assert!(time_end > time_start, "motion must end after it starts");
+        Sphere {
             center: center_start,
             radius,
             material,
             in_motion: true,
-            motion_blur_lerp_factor: center_end - center_start,
-        };
+            motion_blur_lerp_factor: (center_end - center_start) / (time_end - time_start),
+            motion_start_time: time_start,
+        }
     }
     /// Creates a new `Sphere` with a given `center` and `radius`.
     ///
@@ -58,6 +80,7 @@ impl Sphere {
             material,
             in_motion: false,
             motion_blur_lerp_factor: Point3::new(),
+            motion_start_time: 0.0,
         };
     }
 
@@ -69,11 +92,47 @@ impl Sphere {
     /// Returns the center point of the sphere.
     pub fn center(&self, time: f64) -> Point3 {
         if self.in_motion {
-            return self.center + time * self.motion_blur_lerp_factor;
+            return self.center + (time - self.motion_start_time) * self.motion_blur_lerp_factor;
         } else {
             return self.center;
         }
     }
+
+    /// Maps a point on the unit sphere to surface coordinates.
+    ///
+    /// `u` runs from 0 to 1 around the Y axis starting at X = -1, and `v` runs from 0 at
+    /// the bottom pole (Y = -1) to 1 at the top pole (Y = +1).
+    fn surface_coordinates(point: &Point3) -> (f64, f64) {
+        let theta = f64::acos(-point.y());
+        let phi = f64::atan2(-point.z(), point.x()) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    /// Decides whether a ray stops at a hit, passing through with probability `1 - opacity`.
+    fn is_opaque_at(&self, record: &HitRecord) -> bool {
+        let opacity = self.material.opacity(record);
+        opacity >= 1.0 || random_number() < opacity
+    }
+
+    /// Returns the partial derivatives of a point on the sphere with respect to `u` and `v`.
+    ///
+    /// `offset` is the point relative to the center, and `u` and `v` are longitude and latitude
+    /// scaled to run from 0 to 1. That puts `dpdu` along lines of latitude and `dpdv` from pole to
+    /// pole. At the poles, where `dpdu` vanishes, materials fall back to an arbitrary tangent.
+    fn surface_tangents(&self, offset: &Vec3) -> (Vec3, Vec3) {
+        let dpdu = 2.0 * PI * Vec3::from(offset.z(), 0.0, -offset.x());
+        let radial = f64::sqrt(offset.x() * offset.x() + offset.z() * offset.z());
+        if radial <= 0.0 {
+            return (dpdu, Vec3::from(PI * self.radius, 0.0, 0.0));
+        }
+        let dpdv = PI
+            * Vec3::from(
+                -offset.x() * offset.y() / radial,
+                radial,
+                -offset.y() * offset.z() / radial,
+            );
+        (dpdu, dpdv)
+    }
 }
 
 impl Traceable for Sphere {
@@ -108,20 +167,59 @@ impl Traceable for Sphere {
         }
         let discriminant_root = discriminant.sqrt();
 
-        // Find the nearest root that lies in the acceptable range.
-        let mut root = (-half_b - discriminant_root) / a;
-        if !ray_parameter.surrounds(root) {
-            root = (-half_b + discriminant_root) / a;
+        // Take the nearest root that lies in the acceptable range and where the surface is
+        // opaque; rays pass through cut-out parts to the far side of the sphere.
+        let roots = [
+            (-half_b - discriminant_root) / a,
+            (-half_b + discriminant_root) / a,
+        ];
+        for root in roots {
             if !ray_parameter.surrounds(root) {
-                return false;
+                continue;
+            }
+            record.set_parameter(root);
+            record.set_point(ray.at(record.parameter()));
+            let outward_normal: Vec3 = (record.point() - center) / self.radius;
+            record.set_normal_face(ray, &outward_normal);
+            let (u, v) = Self::surface_coordinates(&outward_normal);
+            record.set_uv(u, v);
+            let (dpdu, dpdv) = self.surface_tangents(&(outward_normal * self.radius));
+            record.set_tangents(dpdu, dpdv);
+            record.set_material(self.material.clone());
+            if self.is_opaque_at(record) {
+                return true;
             }
         }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moving(time_start: f64, time_end: f64) -> Sphere {
+        Sphere::moving_between(
+            Point3::from(1.0, 0.0, 0.0),
+            time_start,
+            Point3::from(3.0, 4.0, 0.0),
+            time_end,
+            1.0,
+            Arc::new(Lambertian::new()),
+        )
+    }
+
+    #[test]
+    fn moves_between_keyed_times() {
+        let sphere = moving(2.0, 4.0);
+        assert!((sphere.center(2.0) - Point3::from(1.0, 0.0, 0.0)).length() < 1e-12);
+        assert!((sphere.center(3.0) - Point3::from(2.0, 2.0, 0.0)).length() < 1e-12);
+        assert!((sphere.center(5.0) - Point3::from(4.0, 6.0, 0.0)).length() < 1e-12);
+    }
 
-        record.set_parameter(root);
-        record.set_point(ray.at(record.parameter()));
-        let outward_normal: Vec3 = (record.point() - self.center) / self.radius;
-        record.set_normal_face(ray, &outward_normal);
-        record.set_material(self.material.clone());
-        return true;
+    #[test]
+    #[should_panic(expected = "motion must end after it starts")]
+    fn rejects_empty_motion_interval() {
+        moving(1.0, 1.0);
     }
 }
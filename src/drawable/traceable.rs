@@ -1,6 +1,7 @@
 use std::ops::Neg;
 use std::sync::Arc;
 
+use crate::lights::light::Light;
 use crate::materials;
 use crate::math::interval::Interval;
 use crate::vectors::{
@@ -15,8 +16,13 @@ use materials::{dielectric::Dielectric, lambert::Lambertian, material::Material,
 pub struct HitRecord {
     point: Point3,
     normal: Vec3,
+    geometric_normal: Vec3,
+    dpdu: Vec3,
+    dpdv: Vec3,
     material: Arc<dyn Material>,
     parameter: f64,
+    u: f64,
+    v: f64,
     ray_faces_outside: bool,
 }
 
@@ -36,8 +42,13 @@ impl HitRecord {
         HitRecord {
             point: Point3::new(),
             normal: Vec3::new(),
+            geometric_normal: Vec3::new(),
+            dpdu: Vec3::new(),
+            dpdv: Vec3::new(),
             material: Arc::new(Lambertian::new()),
             parameter: 0.0,
+            u: 0.0,
+            v: 0.0,
             ray_faces_outside: true,
         }
     }
@@ -47,10 +58,28 @@ impl HitRecord {
         self.point
     }
 
-    /// Returns the normal at the intersection.
+    /// Returns the shading normal at the intersection.
+    ///
+    /// This is the normal materials scatter around. It starts out equal to the geometric normal
+    /// but may be bent by normal or bump maps.
     pub fn normal(&self) -> Vec3 {
         self.normal
     }
+
+    /// Returns the true normal of the surface, facing the same side as `normal`.
+    pub fn geometric_normal(&self) -> Vec3 {
+        self.geometric_normal
+    }
+
+    /// Returns the rate of change of the hit point with the `u` surface coordinate.
+    pub fn dpdu(&self) -> Vec3 {
+        self.dpdu
+    }
+
+    /// Returns the rate of change of the hit point with the `v` surface coordinate.
+    pub fn dpdv(&self) -> Vec3 {
+        self.dpdv
+    }
     /// Returns material of object being traced
     pub fn material(&self) -> Arc<dyn Material> {
         self.material.clone()
@@ -61,6 +90,16 @@ impl HitRecord {
         self.parameter
     }
 
+    /// Returns the horizontal surface coordinate of the intersection.
+    pub fn u(&self) -> f64 {
+        self.u
+    }
+
+    /// Returns the vertical surface coordinate of the intersection.
+    pub fn v(&self) -> f64 {
+        self.v
+    }
+
     /// Indicates whether the ray is facing outside the object.
     pub fn ray_faces_outside(&self) -> bool {
         self.ray_faces_outside
@@ -71,7 +110,7 @@ impl HitRecord {
         self.point = point;
     }
 
-    /// Sets the normal at the intersection.
+    /// Sets the shading normal at the intersection, leaving the geometric normal unchanged.
     pub fn set_normal(&mut self, normal: Vec3) {
         self.normal = normal;
     }
@@ -85,12 +124,24 @@ impl HitRecord {
         self.parameter = parameter;
     }
 
+    /// Sets the surface coordinates of the intersection.
+    pub fn set_uv(&mut self, u: f64, v: f64) {
+        self.u = u;
+        self.v = v;
+    }
+
+    /// Sets the surface tangents, the partial derivatives of the hit point with `u` and `v`.
+    pub fn set_tangents(&mut self, dpdu: Vec3, dpdv: Vec3) {
+        self.dpdu = dpdu;
+        self.dpdv = dpdv;
+    }
+
     /// Sets the flag indicating the direction the ray is facing.
     pub fn set_ray_faces_outside(&mut self, ray_faces_outside: bool) {
         self.ray_faces_outside = ray_faces_outside;
     }
 
-    /// Sets the normal orientation based on the incident ray direction.
+    /// Sets the geometric and shading normals, oriented against the incident ray direction.
     pub fn set_normal_face(&mut self, ray: &Ray, outward_normal: &Vec3) {
         outward_normal.normalize();
         self.ray_faces_outside = dot(&ray.direction(), &outward_normal) < 0.0;
@@ -99,6 +150,7 @@ impl HitRecord {
         } else {
             outward_normal.neg()
         };
+        self.geometric_normal = self.normal;
     }
 }
 
@@ -108,9 +160,13 @@ pub trait Traceable: Send + Sync {
     fn hit(&self, ray: &Ray, ray_parameter: Interval, record: &mut HitRecord) -> bool;
 }
 
-/// Holds a collection of ray traceable objects.
+/// Offset keeping shadow rays from hitting the surface they start on or the light they end at.
+const SHADOW_EPSILON: f64 = 0.001;
+
+/// Holds a collection of ray traceable objects and the lights illuminating them.
 pub struct Traceables {
     traceable_objects: Vec<Arc<dyn Traceable>>,
+    lights: Vec<Arc<dyn Light>>,
 }
 
 impl Traceables {
@@ -118,18 +174,41 @@ impl Traceables {
     pub fn new() -> Self {
         Traceables {
             traceable_objects: Vec::new(),
+            lights: Vec::new(),
         }
     }
 
-    /// Empties the collection of traceable objects.
+    /// Empties the collection of traceable objects and lights.
     pub fn clear(&mut self) {
         self.traceable_objects.clear();
+        self.lights.clear();
     }
 
     /// Adds an object to the collection.
     pub fn add(&mut self, object: Arc<dyn Traceable>) {
         self.traceable_objects.push(object);
     }
+
+    /// Adds a punctual light to the scene.
+    pub fn add_light(&mut self, light: Arc<dyn Light>) {
+        self.lights.push(light);
+    }
+
+    /// Returns the punctual lights in the scene.
+    pub fn lights(&self) -> &[Arc<dyn Light>] {
+        &self.lights
+    }
+
+    /// Checks whether anything blocks the segment from `point` along `direction` up to `distance`.
+    pub fn occluded(&self, point: Point3, direction: Vec3, distance: f64, time: f64) -> bool {
+        let shadow_ray = Ray::from(point, direction, time);
+        let mut record = HitRecord::new();
+        self.hit(
+            &shadow_ray,
+            Interval::new(SHADOW_EPSILON, distance - SHADOW_EPSILON),
+            &mut record,
+        )
+    }
 }
 
 impl Traceable for Traceables {
@@ -0,0 +1,41 @@
+use super::light::Light;
+use crate::{color::Color, vector::Point3, vector::Vec3};
+
+/// A light infinitely far away, such as the sun, whose rays all arrive parallel.
+pub struct DirectionalLight {
+    /// Unit vector pointing from the scene toward the light.
+    to_light: Vec3,
+    irradiance: Color,
+}
+
+impl DirectionalLight {
+    pub fn new() -> Self {
+        DirectionalLight {
+            to_light: Vec3::from(0.0, 1.0, 0.0),
+            irradiance: Color::from(1.0, 1.0, 1.0),
+        }
+    }
+
+    /// Creates a directional light shining along `direction` with the given `irradiance`.
+    pub fn from(direction: Vec3, irradiance: Color) -> Self {
+        DirectionalLight {
+            to_light: -direction.normalize(),
+            irradiance,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn illuminate(
+        &self,
+        _point: &Point3,
+        direction: &mut Vec3,
+        distance: &mut f64,
+        radiance: &mut Color,
+    ) -> bool {
+        *direction = self.to_light;
+        *distance = f64::INFINITY;
+        *radiance = self.irradiance;
+        true
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{color::Color, vector::Point3, vector::Vec3};
+
+/// Trait for analytic (delta) light sources.
+///
+/// Punctual lights have no geometry, so rays can never hit them. Instead the
+/// renderer asks each light directly how much light it sends toward a shaded
+/// point and checks visibility with a shadow ray.
+pub trait Light: Send + Sync {
+    /// Samples the light as seen from `point`.
+    ///
+    /// Arguments:
+    /// * `point`: The point being illuminated.
+    /// * `direction`: Set to the unit vector pointing from `point` toward the light.
+    /// * `distance`: Set to the distance to the light (`f64::INFINITY` for lights at infinity).
+    /// * `radiance`: Set to the incident radiance arriving at `point`.
+    ///
+    /// Returns:
+    /// * `bool`: `true` if the light contributes anything at `point`; `false` otherwise.
+    fn illuminate(
+        &self,
+        point: &Point3,
+        direction: &mut Vec3,
+        distance: &mut f64,
+        radiance: &mut Color,
+    ) -> bool;
+}
@@ -0,0 +1,45 @@
+use super::light::Light;
+use crate::{color::Color, vector::Point3, vector::Vec3};
+
+/// A point light radiating equally in every direction with inverse-square falloff.
+pub struct PointLight {
+    position: Point3,
+    intensity: Color,
+}
+
+impl PointLight {
+    pub fn new() -> Self {
+        PointLight {
+            position: Point3::new(),
+            intensity: Color::from(1.0, 1.0, 1.0),
+        }
+    }
+
+    /// Creates a point light at `position` with the given radiant `intensity`.
+    pub fn from(position: Point3, intensity: Color) -> Self {
+        PointLight {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn illuminate(
+        &self,
+        point: &Point3,
+        direction: &mut Vec3,
+        distance: &mut f64,
+        radiance: &mut Color,
+    ) -> bool {
+        let to_light: Vec3 = self.position - *point;
+        let distance_squared = to_light.magnitude();
+        if distance_squared <= 0.0 {
+            return false;
+        }
+        *distance = distance_squared.sqrt();
+        *direction = to_light / *distance;
+        *radiance = self.intensity / distance_squared;
+        true
+    }
+}
@@ -0,0 +1,87 @@
+use super::light::Light;
+use crate::math::rt_math::degrees_to_radians;
+use crate::{
+    color::Color,
+    vector::{dot, Point3, Vec3},
+};
+
+/// A point light restricted to a cone, fading out between an inner and an outer angle.
+pub struct SpotLight {
+    position: Point3,
+    /// Unit vector along the axis of the cone.
+    axis: Vec3,
+    intensity: Color,
+    /// Cosine of the angle inside which the light is at full strength.
+    cos_inner: f64,
+    /// Cosine of the angle beyond which the light is off.
+    cos_outer: f64,
+}
+
+impl SpotLight {
+    pub fn new() -> Self {
+        SpotLight {
+            position: Point3::new(),
+            axis: Vec3::from(0.0, -1.0, 0.0),
+            intensity: Color::from(1.0, 1.0, 1.0),
+            cos_inner: f64::cos(degrees_to_radians(25.0)),
+            cos_outer: f64::cos(degrees_to_radians(30.0)),
+        }
+    }
+
+    /// Creates a spotlight at `position` aimed at `target`.
+    ///
+    /// `inner_angle` and `outer_angle` are half-angles of the cone in degrees.
+    pub fn from(
+        position: Point3,
+        target: Point3,
+        intensity: Color,
+        inner_angle: f64,
+        outer_angle: f64,
+    ) -> Self {
+        let outer_angle = outer_angle.max(inner_angle);
+        SpotLight {
+            position,
+            axis: (target - position).normalize(),
+            intensity,
+            cos_inner: f64::cos(degrees_to_radians(inner_angle)),
+            cos_outer: f64::cos(degrees_to_radians(outer_angle)),
+        }
+    }
+
+    /// Smoothly blends from full strength at the inner cone to zero at the outer cone.
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_inner {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_outer {
+            return 0.0;
+        }
+        let t = (cos_theta - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn illuminate(
+        &self,
+        point: &Point3,
+        direction: &mut Vec3,
+        distance: &mut f64,
+        radiance: &mut Color,
+    ) -> bool {
+        let to_light: Vec3 = self.position - *point;
+        let distance_squared = to_light.magnitude();
+        if distance_squared <= 0.0 {
+            return false;
+        }
+        *distance = distance_squared.sqrt();
+        *direction = to_light / *distance;
+
+        let falloff = self.falloff(dot(&-*direction, &self.axis));
+        if falloff <= 0.0 {
+            return false;
+        }
+        *radiance = falloff * self.intensity / distance_squared;
+        true
+    }
+}
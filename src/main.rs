@@ -1,15 +1,19 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
+use crate::animation::*;
 use crate::drawable::*;
+use crate::lights::*;
 use crate::materials::*;
 use crate::math::*;
 use crate::perspective_camera::camera::Camera;
 use crate::perspective_camera::camera::*;
+use crate::textures::*;
 use crate::traceable::{Traceable, Traceables};
 use crate::vectors::*;
 use drawable::{sphere::Sphere, traceable::HitRecord};
 use materials::{dielectric::Dielectric, lambert::Lambertian, material::Material, metal::Metal};
-use math::rt_math::random_number_in_range;
+use math::rng;
+use math::rt_math::{random_number, random_number_in_range};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use std::{
     path::Path,
@@ -22,16 +26,28 @@ use vectors::{
     vector::{Point3, Vec3},
 };
 
+mod animation;
 mod drawable;
+mod lights;
 mod materials;
 mod math;
 mod perspective_camera;
+mod textures;
 mod vectors;
 
 fn main() {
     if Path::exists(&Path::new("images/image.ppm")) {
         std::fs::remove_file("images/image.ppm").unwrap();
     }
+    // Seed every random number, so the same seed gives the same scene and image; pass
+    // `--seed <n>` for a different one.
+    let seed = env::args()
+        .skip_while(|argument| argument != "--seed")
+        .nth(1)
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or(0);
+    rng::set_seed(seed);
+
     // Setup World
     // The world consists of a ground sphere and a number of randomly placed spheres with different materials.
     let world = Mutex::new(Traceables::new());
@@ -47,14 +63,14 @@ fn main() {
     // Generate a grid of spheres with random positions and materials.
     // Each sphere is given a random radius and position within the grid cell.
     // The radius and position are chosen such that the spheres do not overlap.
-    // (0..=20).into_par_iter().for_each(|_| {
-    (-22..22).into_par_iter().for_each(|a| {
-        (-22..22).into_par_iter().for_each(|b| {
-            let material_factor = rand::random::<f64>();
+    // The grid is filled in order, so the random numbers go to the same spheres every run.
+    for a in -22..22 {
+        for b in -22..22 {
+            let material_factor = random_number();
             let center = Point3::from(
-                a as f64 + 0.9 * rand::random::<f64>(),
+                a as f64 + 0.9 * random_number(),
                 0.2,
-                b as f64 + 0.9 * rand::random::<f64>(),
+                b as f64 + 0.9 * random_number(),
             );
 
             if (center - Point3::from(4.0, 0.2, 0.0)).length() > 0.9 {
@@ -78,7 +94,7 @@ fn main() {
                 // } else if material_factor < 0.95 {
                 //     // metal
                 //     let albedo = Color::random_in_range(0.5, 1.0);
-                //     let fuzz = rand::random::<f64>() * 0.5;
+                //     let fuzz = random_number() * 0.5;
                 //     material = Arc::new(Metal::from(albedo, fuzz));
                 //     // Add the sphere to the world.
                 //     world
@@ -95,9 +111,8 @@ fn main() {
                         .add(Arc::new(Sphere::from(center, 0.2, material)));
                 }
             }
-        });
-    });
-    // });
+        }
+    }
 
     let material1 = Arc::new(Dielectric::from(25.0));
     world.lock().unwrap().add(Arc::new(Sphere::from(
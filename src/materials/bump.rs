@@ -0,0 +1,213 @@
+use super::material::Material;
+use crate::math::onb::Onb;
+use crate::textures::texture::Texture;
+use crate::vector::{dot, Vec3};
+use crate::Color;
+use crate::HitRecord;
+use crate::Ray;
+use std::sync::Arc;
+
+/// Step in surface coordinates used to take finite differences of a bump map.
+const BUMP_DELTA: f64 = 0.0005;
+
+/// Bends the shading normal of a base material with a tangent-space normal map.
+///
+/// The map stores normals as colors, with red along `dpdu`, green along the bitangent and
+/// blue out of the surface, in the usual "OpenGL" convention. Load maps with
+/// `ImageTexture::load_linear` so the values are not gamma decoded.
+pub struct NormalMapped {
+    base: Arc<dyn Material>,
+    normal_map: Arc<dyn Texture>,
+    /// Scales the tangential part of the mapped normals; 1 uses the map as is.
+    strength: f64,
+}
+
+impl NormalMapped {
+    /// Applies `normal_map` to `base` at full strength.
+    pub fn from(base: Arc<dyn Material>, normal_map: Arc<dyn Texture>) -> Self {
+        NormalMapped::with_strength(base, normal_map, 1.0)
+    }
+
+    /// Applies `normal_map` to `base`, exaggerating or softening it by `strength`.
+    pub fn with_strength(
+        base: Arc<dyn Material>,
+        normal_map: Arc<dyn Texture>,
+        strength: f64,
+    ) -> Self {
+        NormalMapped {
+            base,
+            normal_map,
+            strength,
+        }
+    }
+
+    fn shade(&self, ray_in: &Ray, record: &HitRecord) -> HitRecord {
+        let (tangent, bitangent, normal) = tangent_frame(record);
+        let color = self
+            .normal_map
+            .value(record.u(), record.v(), &record.point());
+        let local = 2.0 * color - Color::from(1.0, 1.0, 1.0);
+        let mapped = self.strength * local.x() * tangent
+            + self.strength * local.y() * bitangent
+            + local.z() * normal;
+        shaded(ray_in, record, &mapped)
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered_ray: &mut Ray,
+    ) -> bool {
+        let shaded = self.shade(ray_in, record);
+        self.base
+            .scatter(ray_in, &shaded, attenuation, scattered_ray)
+            && same_side(&shaded, &scattered_ray.direction())
+    }
+
+    fn eval(&self, ray_in: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        let shaded = self.shade(ray_in, record);
+        if !same_side(&shaded, direction) {
+            return Color::new();
+        }
+        self.base.eval(ray_in, &shaded, direction)
+    }
+
+    fn opacity(&self, record: &HitRecord) -> f64 {
+        self.base.opacity(record)
+    }
+}
+
+/// Bends the shading normal of a base material as if its surface were displaced by a height map.
+///
+/// Only the normal changes, so silhouettes stay smooth. The displacement is `scale` times the
+/// grayscale value of `bump`, in world units per unit of surface coordinate.
+pub struct BumpMapped {
+    base: Arc<dyn Material>,
+    bump: Arc<dyn Texture>,
+    scale: f64,
+}
+
+impl BumpMapped {
+    /// Applies the height map `bump`, scaled by `scale`, to `base`.
+    pub fn from(base: Arc<dyn Material>, bump: Arc<dyn Texture>, scale: f64) -> Self {
+        BumpMapped { base, bump, scale }
+    }
+
+    /// Computes the bumped normal from the displaced tangents, as in PBRT's `BumpMap`.
+    ///
+    /// The term for the change of the normal itself across the surface is left out; it only
+    /// matters for displacements that are large relative to the curvature.
+    fn shade(&self, ray_in: &Ray, record: &HitRecord) -> HitRecord {
+        let (tangent, bitangent, normal) = tangent_frame(record);
+        let (u, v) = (record.u(), record.v());
+        let point = record.point();
+        let (dpdu, dpdv) = surface_tangents(record, &tangent, &bitangent);
+
+        let height = self.scale * self.bump.scalar(u, v, &point);
+        let height_u = self.scale
+            * self
+                .bump
+                .scalar(u + BUMP_DELTA, v, &(point + BUMP_DELTA * dpdu));
+        let height_v = self.scale
+            * self
+                .bump
+                .scalar(u, v + BUMP_DELTA, &(point + BUMP_DELTA * dpdv));
+
+        let bumped_dpdu = dpdu + (height_u - height) / BUMP_DELTA * normal;
+        let bumped_dpdv = dpdv + (height_v - height) / BUMP_DELTA * normal;
+        let mut bumped = Vec3::cross(&bumped_dpdu, &bumped_dpdv);
+        if dot(&bumped, &normal) < 0.0 {
+            bumped = -bumped;
+        }
+        shaded(ray_in, record, &bumped)
+    }
+}
+
+impl Material for BumpMapped {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered_ray: &mut Ray,
+    ) -> bool {
+        let shaded = self.shade(ray_in, record);
+        self.base
+            .scatter(ray_in, &shaded, attenuation, scattered_ray)
+            && same_side(&shaded, &scattered_ray.direction())
+    }
+
+    fn eval(&self, ray_in: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        let shaded = self.shade(ray_in, record);
+        if !same_side(&shaded, direction) {
+            return Color::new();
+        }
+        self.base.eval(ray_in, &shaded, direction)
+    }
+
+    fn opacity(&self, record: &HitRecord) -> f64 {
+        self.base.opacity(record)
+    }
+}
+
+/// Returns an orthonormal tangent, bitangent and normal around the outward shading normal.
+///
+/// The tangent follows `dpdu` where the surface provides one. Working with the outward normal
+/// keeps maps from flipping when a surface is seen from the inside.
+fn tangent_frame(record: &HitRecord) -> (Vec3, Vec3, Vec3) {
+    let normal = match record.ray_faces_outside() {
+        true => record.normal(),
+        _ => -record.normal(),
+    };
+    let projected = record.dpdu() - dot(&record.dpdu(), &normal) * normal;
+    let tangent = match projected.length() > 1e-9 {
+        true => projected.normalize(),
+        _ => Onb::from_w(&normal).u(),
+    };
+    let bitangent = Vec3::cross(&normal, &tangent);
+    (tangent, bitangent, normal)
+}
+
+/// Returns the surface tangents, replacing missing ones with the unit-length frame axes.
+fn surface_tangents(record: &HitRecord, tangent: &Vec3, bitangent: &Vec3) -> (Vec3, Vec3) {
+    let dpdu = match record.dpdu().length() > 1e-9 {
+        true => record.dpdu(),
+        _ => *tangent,
+    };
+    let dpdv = match record.dpdv().length() > 1e-9 {
+        true => record.dpdv(),
+        _ => *bitangent,
+    };
+    (dpdu, dpdv)
+}
+
+/// Returns a copy of `record` whose shading normal is the outward-facing `normal`.
+///
+/// If the new normal would face away from the viewer the surface could not be seen with it,
+/// so the record is returned unchanged instead.
+fn shaded(ray_in: &Ray, record: &HitRecord, normal: &Vec3) -> HitRecord {
+    if normal.length() <= 0.0 {
+        return record.clone();
+    }
+    let mut normal = normal.normalize();
+    if !record.ray_faces_outside() {
+        normal = -normal;
+    }
+    if dot(&ray_in.direction(), &normal) >= 0.0 {
+        return record.clone();
+    }
+    let mut shaded = record.clone();
+    shaded.set_normal(normal);
+    shaded
+}
+
+/// Checks that `direction` lies on the same side of the geometric and shading normals.
+///
+/// Directions that disagree would let light leak through the surface, so they are discarded.
+fn same_side(record: &HitRecord, direction: &Vec3) -> bool {
+    (dot(direction, &record.geometric_normal()) > 0.0) == (dot(direction, &record.normal()) > 0.0)
+}
@@ -0,0 +1,148 @@
+use super::fresnel::fresnel_conductor;
+use super::material::Material;
+use super::microfacet::TrowbridgeReitz;
+use crate::math::onb::Onb;
+use crate::math::rt_math::random_number;
+use crate::spectrum;
+use crate::Color;
+use crate::HitRecord;
+use crate::Ray;
+use crate::Vec3;
+
+/// A physically based metal using the GGX microfacet model.
+///
+/// Reflectance comes from the exact Fresnel equations for a complex index of refraction
+/// `eta + ik`, given per color channel, so tinted metals darken and shift hue correctly at
+/// grazing angles. Roughness may differ along the two tangent directions for brushed looks.
+pub struct Conductor {
+    eta: Color,
+    k: Color,
+    distribution: TrowbridgeReitz,
+}
+
+impl Conductor {
+    pub fn new() -> Self {
+        Conductor::from(Color::from(1.0, 1.0, 1.0), Color::new(), 0.0)
+    }
+
+    /// Creates an isotropic conductor.
+    ///
+    /// * `eta`: Real part of the index of refraction, per channel.
+    /// * `k`: Absorption coefficient, per channel.
+    /// * `roughness`: Perceptual roughness in `[0, 1]`; `0` is a perfect mirror.
+    pub fn from(eta: Color, k: Color, roughness: f64) -> Self {
+        Conductor::anisotropic(eta, k, roughness, roughness)
+    }
+
+    /// Creates a conductor with separate roughness along each tangent direction.
+    ///
+    /// `roughness_u` applies along the surface's `u` direction (`HitRecord::dpdu`), such as
+    /// around a sphere's equator, and `roughness_v` across it.
+    pub fn anisotropic(eta: Color, k: Color, roughness_u: f64, roughness_v: f64) -> Self {
+        Conductor {
+            eta,
+            k,
+            distribution: TrowbridgeReitz::from(
+                TrowbridgeReitz::roughness_to_alpha(roughness_u),
+                TrowbridgeReitz::roughness_to_alpha(roughness_v),
+            ),
+        }
+    }
+
+    pub fn gold(roughness: f64) -> Self {
+        Conductor::from(
+            Color::from(0.143119, 0.374957, 1.44248),
+            Color::from(3.98316, 2.38572, 1.60322),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Conductor::from(
+            Color::from(0.200438, 0.924033, 1.10221),
+            Color::from(3.91295, 2.45285, 2.14219),
+            roughness,
+        )
+    }
+
+    pub fn aluminum(roughness: f64) -> Self {
+        Conductor::from(
+            Color::from(1.65746, 0.880369, 0.521229),
+            Color::from(9.22387, 6.26952, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Conductor::from(
+            Color::from(0.155265, 0.116723, 0.138342),
+            Color::from(4.82835, 3.12225, 2.14696),
+            roughness,
+        )
+    }
+
+    /// Per-channel Fresnel reflectance for the given cosine of incidence.
+    ///
+    /// In spectral mode the RGB optical constants are uplifted to the ray's wavelengths.
+    fn fresnel(&self, ray_in: &Ray, cos_theta: f64) -> Color {
+        let eta = spectrum::uplift(ray_in, self.eta);
+        let k = spectrum::uplift(ray_in, self.k);
+        Color::from(
+            fresnel_conductor(cos_theta, eta.x(), k.x()),
+            fresnel_conductor(cos_theta, eta.y(), k.y()),
+            fresnel_conductor(cos_theta, eta.z(), k.z()),
+        )
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered_ray: &mut Ray,
+    ) -> bool {
+        let frame = Onb::from_w_tangent(&record.normal(), &record.dpdu());
+        let wo = frame.local_from_world(&-ray_in.direction().normalize());
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        if self.distribution.effectively_smooth() {
+            let wi = Vec3::from(-wo.x(), -wo.y(), wo.z());
+            *scattered_ray = Ray::from(record.point(), frame.world_from_local(&wi), ray_in.time());
+            *attenuation = self.fresnel(ray_in, wo.z());
+            return true;
+        }
+
+        let wm = self
+            .distribution
+            .sample_wm(&wo, random_number(), random_number());
+        let wi = Vec3::reflect(&-wo, &wm);
+        if wi.z() <= 0.0 {
+            return false;
+        }
+
+        // With visible normal sampling the D and cosine terms cancel, leaving F * G / G1.
+        *attenuation = self.fresnel(ray_in, wo.dot(&wm))
+            * (self.distribution.g(&wo, &wi) / self.distribution.g1(&wo));
+        *scattered_ray = Ray::from(record.point(), frame.world_from_local(&wi), ray_in.time());
+        true
+    }
+
+    fn eval(&self, ray_in: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        if self.distribution.effectively_smooth() {
+            return Color::new();
+        }
+        let frame = Onb::from_w_tangent(&record.normal(), &record.dpdu());
+        let wo = frame.local_from_world(&-ray_in.direction().normalize());
+        let wi = frame.local_from_world(direction);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::new();
+        }
+        let wm = (wo + wi).normalize();
+        self.fresnel(ray_in, wo.dot(&wm))
+            * (self.distribution.d(&wm) * self.distribution.g(&wo, &wi) / (4.0 * wo.z()))
+    }
+}
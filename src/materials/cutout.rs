@@ -0,0 +1,54 @@
+use super::material::Material;
+use crate::textures::{solid_color::SolidColor, texture::Texture};
+use crate::Color;
+use crate::HitRecord;
+use crate::Ray;
+use crate::Vec3;
+use std::sync::Arc;
+
+/// Cuts holes in a base material with an opacity mask, for leaves, fences and decals.
+///
+/// Where the mask is 0 rays pass straight through the surface; where it is 1 the base material
+/// is used as is. Values in between are partially transparent.
+pub struct Cutout {
+    base: Arc<dyn Material>,
+    /// Opacity in `[0, 1]`, read as a grayscale value.
+    mask: Arc<dyn Texture>,
+}
+
+impl Cutout {
+    /// Makes `base` uniformly `opacity` opaque.
+    pub fn from(base: Arc<dyn Material>, opacity: f64) -> Self {
+        Cutout::masked(base, Arc::new(SolidColor::from_value(opacity)))
+    }
+
+    /// Makes `base` as opaque as the grayscale `mask` says.
+    pub fn masked(base: Arc<dyn Material>, mask: Arc<dyn Texture>) -> Self {
+        Cutout { base, mask }
+    }
+}
+
+impl Material for Cutout {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered_ray: &mut Ray,
+    ) -> bool {
+        self.base
+            .scatter(ray_in, record, attenuation, scattered_ray)
+    }
+
+    fn eval(&self, ray_in: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        self.base.eval(ray_in, record, direction)
+    }
+
+    fn opacity(&self, record: &HitRecord) -> f64 {
+        let opacity = self
+            .mask
+            .scalar(record.u(), record.v(), &record.point())
+            .clamp(0.0, 1.0);
+        opacity * self.base.opacity(record)
+    }
+}
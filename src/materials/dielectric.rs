@@ -1,6 +1,7 @@
 use super::material::Material;
 use crate::materials;
 use crate::rt_math::*;
+use crate::spectrum::{self, Wavelengths};
 use crate::vector::*;
 use crate::Color;
 use crate::HitRecord;
@@ -8,20 +9,147 @@ use crate::Ray;
 use crate::Vec3;
 use std::ops::Neg;
 
+/// A clear or colored glass-like material that both reflects and refracts.
+///
+/// Light traveling through the interior is absorbed following the Beer–Lambert law, so
+/// thicker parts of a tinted object take on a deeper color.
+///
+/// The index of refraction may vary with wavelength; in spectral mode this splits white light
+/// into its colors (dispersion). In RGB mode the index at 550 nm is used.
 pub struct Dielectric {
     index_of_refraction: f64,
+    /// Per-channel absorption coefficient, in inverse scene units.
+    absorption: Color,
+    dispersion: Dispersion,
+}
+
+/// Describes how a dielectric's index of refraction varies with wavelength.
+#[derive(Clone, Copy)]
+pub enum Dispersion {
+    /// The index of refraction is the same at every wavelength.
+    None,
+    /// Cauchy's equation `n = a + b / λ²`, with `λ` in micrometers.
+    Cauchy { a: f64, b: f64 },
+    /// The Sellmeier equation `n² = 1 + Σ bᵢ λ² / (λ² - cᵢ)`, with `λ` in micrometers.
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    /// Evaluates the index of refraction at `wavelength` nanometers.
+    ///
+    /// Returns `None` if the index does not depend on wavelength.
+    pub fn index_of_refraction(&self, wavelength: f64) -> Option<f64> {
+        let micrometers = wavelength / 1000.0;
+        let lambda2 = micrometers * micrometers;
+        match self {
+            Dispersion::None => None,
+            Dispersion::Cauchy { a, b } => Some(a + b / lambda2),
+            Dispersion::Sellmeier { b, c } => {
+                let mut n2 = 1.0;
+                for i in 0..3 {
+                    n2 += b[i] * lambda2 / (lambda2 - c[i]);
+                }
+                Some(n2.sqrt())
+            }
+        }
+    }
 }
 
 impl Dielectric {
     pub fn new() -> Self {
         Dielectric {
             index_of_refraction: 0.0,
+            absorption: Color::new(),
+            dispersion: Dispersion::None,
         }
     }
     pub fn from(index_of_refraction: f64) -> Self {
         Dielectric {
             index_of_refraction,
+            absorption: Color::new(),
+            dispersion: Dispersion::None,
+        }
+    }
+
+    /// Creates a dielectric that absorbs light passing through it.
+    ///
+    /// * `absorption`: Per-channel absorption coefficient; light surviving a path of length
+    ///   `d` through the interior is scaled by `exp(-absorption * d)`.
+    pub fn absorbing(index_of_refraction: f64, absorption: Color) -> Self {
+        Dielectric {
+            index_of_refraction,
+            absorption,
+            dispersion: Dispersion::None,
+        }
+    }
+
+    /// Creates a dispersive dielectric whose index of refraction follows Cauchy's equation
+    /// `n = a + b / λ²`, with `λ` in micrometers.
+    pub fn cauchy(a: f64, b: f64) -> Self {
+        Dielectric::dispersive(Dispersion::Cauchy { a, b })
+    }
+
+    /// Creates a dispersive dielectric whose index of refraction follows the Sellmeier
+    /// equation, with `c` in square micrometers.
+    pub fn sellmeier(b: [f64; 3], c: [f64; 3]) -> Self {
+        Dielectric::dispersive(Dispersion::Sellmeier { b, c })
+    }
+
+    /// Schott N-BK7, a common optical crown glass.
+    pub fn bk7() -> Self {
+        Dielectric::sellmeier(
+            [1.03961212, 0.231792344, 1.01046945],
+            [0.00600069867, 0.0200179144, 103.560653],
+        )
+    }
+
+    /// Diamond, whose strong dispersion gives it "fire".
+    pub fn diamond() -> Self {
+        Dielectric::sellmeier([0.3306, 4.3356, 0.0], [0.030625, 0.011236, 0.0])
+    }
+
+    fn dispersive(dispersion: Dispersion) -> Self {
+        Dielectric {
+            index_of_refraction: dispersion.index_of_refraction(550.0).unwrap_or(1.0),
+            absorption: Color::new(),
+            dispersion,
+        }
+    }
+
+    /// Returns a copy of this dielectric that also absorbs light passing through it.
+    pub fn with_absorption(mut self, absorption: Color) -> Self {
+        self.absorption = absorption;
+        self
+    }
+
+    /// Creates a tinted dielectric from the color white light takes on after traveling
+    /// `distance` units through it, which is easier to art-direct than a coefficient.
+    pub fn tinted(index_of_refraction: f64, color: Color, distance: f64) -> Self {
+        let coefficient = |channel: f64| -channel.clamp(1e-6, 1.0).ln() / distance;
+        Dielectric::absorbing(
+            index_of_refraction,
+            Color::from(
+                coefficient(color.x()),
+                coefficient(color.y()),
+                coefficient(color.z()),
+            ),
+        )
+    }
+
+    /// Fraction of light surviving the trip from the ray's origin to the hit point.
+    ///
+    /// Only rays hitting the surface from the inside have traveled through the material.
+    fn transmittance(&self, ray_in: &Ray, record: &HitRecord) -> Color {
+        if record.ray_faces_outside() {
+            return Color::from(1.0, 1.0, 1.0);
         }
+        let distance = record.parameter() * ray_in.direction().length();
+        let absorption = spectrum::uplift(ray_in, self.absorption);
+        Color::from(
+            (-absorption.x() * distance).exp(),
+            (-absorption.y() * distance).exp(),
+            (-absorption.z() * distance).exp(),
+        )
     }
 }
 
@@ -33,10 +161,24 @@ impl Material for Dielectric {
         attenuation: &mut Color,
         scattered: &mut Ray,
     ) -> bool {
-        *attenuation = Color::from(1.0, 1.0, 1.0);
+        *attenuation = self.transmittance(ray_in, record);
+
+        // In spectral mode a dispersive dielectric bends each wavelength differently, so only
+        // the hero wavelength can be followed from here on.
+        let mut index_of_refraction = self.index_of_refraction;
+        let mut wavelengths: Option<Wavelengths> = ray_in.wavelengths();
+        if let Some(carried) = wavelengths {
+            if let Some(ior) = self.dispersion.index_of_refraction(carried.hero()) {
+                let (terminated, weight) = carried.terminate_secondary();
+                index_of_refraction = ior;
+                wavelengths = Some(terminated);
+                *attenuation = *attenuation * weight;
+            }
+        }
+
         let refraction_ratio: f64 = match record.ray_faces_outside() {
-            true => 1.0 / self.index_of_refraction,
-            _ => self.index_of_refraction,
+            true => 1.0 / index_of_refraction,
+            _ => index_of_refraction,
         };
         let ray_in_unit_direction: Vec3 = ray_in.direction().normalize();
         let cos_theta: f64 = f64::min(dot(&ray_in_unit_direction.neg(), &record.normal()), 1.0);
@@ -50,6 +192,7 @@ impl Material for Dielectric {
             direction = Vec3::refract(&ray_in_unit_direction, &record.normal(), refraction_ratio);
         }
         *scattered = Ray::from(record.point(), direction, ray_in.time());
+        scattered.set_wavelengths(wavelengths);
         return true;
     }
 }
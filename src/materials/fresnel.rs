@@ -0,0 +1,300 @@
+use crate::vector::{dot, Vec3};
+use std::ops::{Add, Div, Mul, Sub};
+
+/// A minimal complex number, just enough to evaluate Fresnel equations for conductors.
+#[derive(Clone, Copy)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn from(re: f64, im: f64) -> Self {
+        Complex { re, im }
+    }
+
+    /// Returns `e^(iθ)`.
+    fn from_phase(theta: f64) -> Self {
+        Complex::from(theta.cos(), theta.sin())
+    }
+
+    /// Squared magnitude.
+    fn norm(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// Principal square root.
+    fn sqrt(self) -> Complex {
+        let n = self.norm().sqrt();
+        if n == 0.0 {
+            return Complex::from(0.0, 0.0);
+        }
+        let t1 = (0.5 * (n + self.re.abs())).sqrt();
+        let t2 = 0.5 * self.im / t1;
+        if self.re >= 0.0 {
+            Complex::from(t1, t2)
+        } else {
+            Complex::from(t2.abs(), t1.copysign(self.im))
+        }
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, other: Complex) -> Complex {
+        Complex::from(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, other: Complex) -> Complex {
+        Complex::from(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, other: Complex) -> Complex {
+        Complex::from(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+    fn div(self, other: Complex) -> Complex {
+        let scale = 1.0 / other.norm();
+        Complex::from(
+            scale * (self.re * other.re + self.im * other.im),
+            scale * (self.im * other.re - self.re * other.im),
+        )
+    }
+}
+
+/// Unpolarized Fresnel reflectance of a conductor with complex index of refraction `eta + ik`.
+///
+/// * `cos_theta_i`: Cosine of the angle between the incident direction and the normal.
+/// * `eta`: Real part of the relative index of refraction.
+/// * `k`: Absorption coefficient (imaginary part of the index of refraction).
+///
+/// Reference: Pharr, Jakob, Humphreys, "Physically Based Rendering", 4th ed., §9.3.6.
+pub fn fresnel_conductor(cos_theta_i: f64, eta: f64, k: f64) -> f64 {
+    let eta = Complex::from(eta, k);
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin2_theta_i = Complex::from(1.0 - cos_theta_i * cos_theta_i, 0.0);
+    let sin2_theta_t = sin2_theta_i / (eta * eta);
+    let cos_theta_t = (Complex::from(1.0, 0.0) - sin2_theta_t).sqrt();
+    let cos_i = Complex::from(cos_theta_i, 0.0);
+
+    let r_parallel = (eta * cos_i - cos_theta_t) / (eta * cos_i + cos_theta_t);
+    let r_perpendicular = (cos_i - eta * cos_theta_t) / (cos_i + eta * cos_theta_t);
+    (r_parallel.norm() + r_perpendicular.norm()) / 2.0
+}
+
+/// Unpolarized reflectance of a thin film coating, including interference between the light
+/// reflected at its top and bottom surfaces.
+///
+/// * `cos_theta_i`: Cosine of the angle of incidence in the outer medium.
+/// * `outer_ior`: Index of refraction of the medium the light arrives from.
+/// * `film_ior`: Index of refraction of the film.
+/// * `base_eta`, `base_k`: Complex index of refraction of the material under the film
+///   (`base_k` is zero for dielectrics).
+/// * `thickness`: Thickness of the film, in nanometers.
+/// * `wavelength`: Wavelength of the light, in nanometers.
+///
+/// Reference: Born and Wolf, "Principles of Optics", §7.6 (Airy summation).
+pub fn fresnel_thin_film(
+    cos_theta_i: f64,
+    outer_ior: f64,
+    film_ior: f64,
+    base_eta: f64,
+    base_k: f64,
+    thickness: f64,
+    wavelength: f64,
+) -> f64 {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin2_theta_i = 1.0 - cos_theta_i * cos_theta_i;
+
+    // Snell's law into the film; light that cannot enter the film is totally reflected.
+    let sin2_theta_film = sin2_theta_i * (outer_ior / film_ior) * (outer_ior / film_ior);
+    if sin2_theta_film >= 1.0 {
+        return 1.0;
+    }
+    let cos_theta_film = (1.0 - sin2_theta_film).sqrt();
+
+    // The base may be absorbing, so the angle inside it is complex.
+    let n1 = Complex::from(outer_ior, 0.0);
+    let n2 = Complex::from(film_ior, 0.0);
+    let n3 = Complex::from(base_eta, base_k);
+    let sin2_theta_base = Complex::from(sin2_theta_i * outer_ior * outer_ior, 0.0) / (n3 * n3);
+    let cos_theta_base = (Complex::from(1.0, 0.0) - sin2_theta_base).sqrt();
+    let c1 = Complex::from(cos_theta_i, 0.0);
+    let c2 = Complex::from(cos_theta_film, 0.0);
+    let c3 = cos_theta_base;
+
+    // Amplitude reflection coefficients at the top and bottom of the film.
+    let r12_s = (n1 * c1 - n2 * c2) / (n1 * c1 + n2 * c2);
+    let r12_p = (n2 * c1 - n1 * c2) / (n2 * c1 + n1 * c2);
+    let r23_s = (n2 * c2 - n3 * c3) / (n2 * c2 + n3 * c3);
+    let r23_p = (n3 * c2 - n2 * c3) / (n3 * c2 + n2 * c3);
+
+    // Phase difference accumulated by one round trip through the film.
+    let phase = Complex::from_phase(
+        4.0 * std::f64::consts::PI * film_ior * thickness * cos_theta_film / wavelength,
+    );
+    let one = Complex::from(1.0, 0.0);
+    let r_s = (r12_s + r23_s * phase) / (one + r12_s * r23_s * phase);
+    let r_p = (r12_p + r23_p * phase) / (one + r12_p * r23_p * phase);
+    ((r_s.norm() + r_p.norm()) / 2.0).min(1.0)
+}
+
+/// Unpolarized Fresnel reflectance at a smooth boundary between two dielectrics.
+///
+/// * `cos_theta_i`: Cosine of the angle between the incident direction and the normal.
+///   Negative values mean the light arrives from the other side of the boundary.
+/// * `eta`: Index of refraction on the far side of the normal divided by the near side.
+///
+/// Returns `1.0` under total internal reflection.
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let mut cos_theta_i = cos_theta_i.clamp(-1.0, 1.0);
+    let mut eta = eta;
+    if cos_theta_i < 0.0 {
+        eta = 1.0 / eta;
+        cos_theta_i = -cos_theta_i;
+    }
+
+    let sin2_theta_i = 1.0 - cos_theta_i * cos_theta_i;
+    let sin2_theta_t = sin2_theta_i / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+/// Refracts `wi` through a boundary with normal `normal` on the same side as `wi`.
+///
+/// Both vectors point away from the surface. `eta` is the index of refraction on the far side
+/// divided by the near side. Returns `None` under total internal reflection.
+pub fn refract(wi: &Vec3, normal: &Vec3, eta: f64) -> Option<Vec3> {
+    let cos_theta_i = dot(normal, wi);
+    let sin2_theta_i = (1.0 - cos_theta_i * cos_theta_i).max(0.0);
+    let sin2_theta_t = sin2_theta_i / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return None;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+    Some(-*wi / eta + (cos_theta_i / eta - cos_theta_t) * *normal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} vs {expected}");
+    }
+
+    #[test]
+    fn conductor_at_normal_incidence() {
+        let (eta, k) = (0.2, 3.9);
+        let expected = ((eta - 1.0) * (eta - 1.0) + k * k) / ((eta + 1.0) * (eta + 1.0) + k * k);
+        assert_close(fresnel_conductor(1.0, eta, k), expected);
+    }
+
+    #[test]
+    fn conductor_reflects_everything_at_grazing_incidence() {
+        assert_close(fresnel_conductor(0.0, 0.2, 3.9), 1.0);
+        assert_close(fresnel_conductor(0.0, 1.5, 0.0), 1.0);
+    }
+
+    #[test]
+    fn conductor_without_absorption_is_a_dielectric() {
+        // The same equations with a real index of refraction: 4% at normal incidence for glass.
+        assert_close(fresnel_conductor(1.0, 1.5, 0.0), 0.04);
+        // At Brewster's angle parallel polarized light is not reflected at all, leaving half
+        // the perpendicular reflectance.
+        let cos_i = f64::atan(1.5).cos();
+        let cos_t = (1.0 - (1.0 - cos_i * cos_i) / (1.5 * 1.5)).sqrt();
+        let perpendicular = ((cos_i - 1.5 * cos_t) / (cos_i + 1.5 * cos_t)).powi(2);
+        assert_close(fresnel_conductor(cos_i, 1.5, 0.0), perpendicular / 2.0);
+    }
+
+    #[test]
+    fn dielectric_at_normal_and_grazing_incidence() {
+        assert_close(fresnel_dielectric(1.0, 1.5), 0.04);
+        assert_close(fresnel_dielectric(-1.0, 1.5), 0.04);
+        assert_close(fresnel_dielectric(0.0, 1.5), 1.0);
+    }
+
+    #[test]
+    fn dielectric_matches_conductor_without_absorption() {
+        for cos_theta in [0.1, 0.5, 0.9] {
+            assert_close(
+                fresnel_dielectric(cos_theta, 1.33),
+                fresnel_conductor(cos_theta, 1.33, 0.0),
+            );
+        }
+    }
+
+    #[test]
+    fn dielectric_reflects_totally_past_the_critical_angle() {
+        // Leaving glass, the critical angle is asin(1 / 1.5), about 41.8 degrees.
+        let critical = f64::asin(1.0 / 1.5).cos();
+        assert_close(fresnel_dielectric(-(critical - 0.01), 1.5), 1.0);
+        assert!(fresnel_dielectric(-(critical + 0.01), 1.5) < 1.0);
+    }
+
+    #[test]
+    fn thin_film_of_zero_thickness_leaves_the_base() {
+        for cos_theta in [1.0, 0.7, 0.2] {
+            assert_close(
+                fresnel_thin_film(cos_theta, 1.0, 1.33, 1.5, 0.0, 0.0, 550.0),
+                fresnel_dielectric(cos_theta, 1.5),
+            );
+            assert_close(
+                fresnel_thin_film(cos_theta, 1.0, 1.33, 0.2, 3.9, 0.0, 550.0),
+                fresnel_conductor(cos_theta, 0.2, 3.9),
+            );
+        }
+    }
+
+    #[test]
+    fn thin_film_matching_the_outer_medium_is_invisible() {
+        assert_close(
+            fresnel_thin_film(0.6, 1.0, 1.0, 1.5, 0.0, 321.0, 480.0),
+            fresnel_dielectric(0.6, 1.5),
+        );
+    }
+
+    #[test]
+    fn quarter_wave_film_cancels_reflection_at_normal_incidence() {
+        // An anti-reflection coating: the film's index is the geometric mean of its
+        // neighbors' and it is a quarter of a wavelength thick inside the film.
+        let film_ior = f64::sqrt(1.5);
+        let thickness = 550.0 / (4.0 * film_ior);
+        assert_close(
+            fresnel_thin_film(1.0, 1.0, film_ior, 1.5, 0.0, thickness, 550.0),
+            0.0,
+        );
+        // Half a wavelength thick, the film drops out again.
+        assert_close(
+            fresnel_thin_film(1.0, 1.0, film_ior, 1.5, 0.0, 2.0 * thickness, 550.0),
+            0.04,
+        );
+    }
+
+    #[test]
+    fn thin_film_reflects_everything_at_grazing_incidence() {
+        assert_close(
+            fresnel_thin_film(0.0, 1.0, 1.33, 1.5, 0.0, 300.0, 550.0),
+            1.0,
+        );
+    }
+}
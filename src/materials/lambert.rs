@@ -1,4 +1,7 @@
 use crate::materials;
+use crate::math::rt_math::PI;
+use crate::spectrum;
+use crate::vector::dot;
 use crate::Color;
 use crate::HitRecord;
 use crate::Ray;
@@ -34,7 +37,15 @@ impl Material for Lambertian {
             scatter_direction = record.normal();
         }
         *scattered_ray = Ray::from(record.point(), scatter_direction, ray_in.time());
-        *attenuation = self.albedo;
+        *attenuation = spectrum::uplift(ray_in, self.albedo);
         return true;
     }
+
+    fn eval(&self, ray_in: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        let cos_theta = dot(&record.normal(), direction);
+        if cos_theta <= 0.0 {
+            return Color::new();
+        }
+        spectrum::uplift(ray_in, self.albedo) * (cos_theta / PI)
+    }
 }
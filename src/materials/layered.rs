@@ -0,0 +1,165 @@
+use super::fresnel::fresnel_dielectric;
+use super::material::Material;
+use crate::math::rt_math::random_number;
+use crate::spectrum;
+use crate::vector::dot;
+use crate::Color;
+use crate::HitRecord;
+use crate::Ray;
+use crate::Vec3;
+use std::sync::Arc;
+
+/// A smooth dielectric coating, such as varnish or the clear coat of car paint, over any base.
+///
+/// Light either reflects off the coating, in proportion to its Fresnel reflectance, or
+/// refracts into it and reaches the base. Light the base sends back up refracts out again,
+/// losing what the coating reflects back down on the way, plus anything the coating absorbs
+/// along its slanted paths, so the combination never reflects more than the base alone.
+pub struct Layered {
+    base: Arc<dyn Material>,
+    coat_ior: f64,
+    /// Fraction of light surviving one trip straight through the coating, per channel.
+    coat_color: Color,
+}
+
+impl Layered {
+    /// Coats `base` with a clear layer of the given index of refraction.
+    pub fn from(base: Arc<dyn Material>, coat_ior: f64) -> Self {
+        Layered::tinted(base, coat_ior, Color::from(1.0, 1.0, 1.0))
+    }
+
+    /// Coats `base` with a colored layer; `coat_color` is the fraction of light that survives
+    /// one pass straight through it.
+    pub fn tinted(base: Arc<dyn Material>, coat_ior: f64, coat_color: Color) -> Self {
+        Layered {
+            base,
+            coat_ior,
+            coat_color,
+        }
+    }
+
+    /// Fraction of light surviving `path_length` coating thicknesses.
+    fn coat_transmittance(&self, ray_in: &Ray, path_length: f64) -> Color {
+        let coat_color = spectrum::uplift(ray_in, self.coat_color);
+        Color::from(
+            coat_color.x().max(0.0).powf(path_length),
+            coat_color.y().max(0.0).powf(path_length),
+            coat_color.z().max(0.0).powf(path_length),
+        )
+    }
+
+    /// Length of a trip through the coating, in coating thicknesses, for light traveling at
+    /// `cos_theta` to the normal inside it.
+    fn path_length(&self, cos_theta: f64) -> f64 {
+        1.0 / cos_theta.max(1e-2)
+    }
+
+    /// Returns `ray_in` bent into the coating, which is the ray the base sees.
+    fn refracted_ray(&self, ray_in: &Ray, record: &HitRecord) -> Ray {
+        let direction = Vec3::refract(
+            &ray_in.direction().normalize(),
+            &record.normal(),
+            1.0 / self.coat_ior,
+        );
+        let mut refracted = Ray::from(ray_in.origin(), direction, ray_in.time());
+        refracted.set_wavelengths(ray_in.wavelengths());
+        refracted
+    }
+
+    /// Bends `direction`, leaving the surface above the coating, to the direction it has
+    /// inside the coating.
+    fn bend_into_coat(&self, direction: &Vec3, normal: &Vec3) -> Vec3 {
+        -Vec3::refract(&-*direction, normal, 1.0 / self.coat_ior)
+    }
+
+    /// Bends the unit `direction`, leaving the base inside the coating, to the direction it
+    /// leaves the coating in, or returns `None` if the coating reflects it back down.
+    fn bend_out_of_coat(&self, direction: &Vec3, normal: &Vec3) -> Option<Vec3> {
+        let cos_theta = dot(direction, normal);
+        if self.coat_ior * self.coat_ior * (1.0 - cos_theta * cos_theta) >= 1.0 {
+            return None;
+        }
+        Some(Vec3::refract(direction, &-*normal, self.coat_ior))
+    }
+}
+
+impl Material for Layered {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered_ray: &mut Ray,
+    ) -> bool {
+        let unit_direction: Vec3 = ray_in.direction().normalize();
+        let normal = record.normal();
+        let cos_in = dot(&-unit_direction, &normal).clamp(0.0, 1.0);
+
+        // Reflecting off the coating and passing into it are chosen by the Fresnel
+        // reflectance, which cancels the reflectance from the weights.
+        if random_number() < fresnel_dielectric(cos_in, self.coat_ior) {
+            let reflected = Vec3::reflect(&unit_direction, &normal);
+            *scattered_ray = Ray::from(record.point(), reflected, ray_in.time());
+            *attenuation = Color::from(1.0, 1.0, 1.0);
+            return true;
+        }
+
+        // The base scatters the light that refracted into the coating.
+        let refracted = self.refracted_ray(ray_in, record);
+        if !self
+            .base
+            .scatter(&refracted, record, attenuation, scattered_ray)
+        {
+            return false;
+        }
+        let cos_in = dot(&-refracted.direction().normalize(), &normal);
+        let direction = scattered_ray.direction().normalize();
+        let cos_out = dot(&direction, &normal);
+        if cos_out <= 0.0 {
+            // The base transmitted the light, which crossed the coating only once.
+            *attenuation = *attenuation * self.coat_transmittance(ray_in, self.path_length(cos_in));
+            return true;
+        }
+
+        // Light the coating reflects back down is lost to the base.
+        let Some(leaving) = self.bend_out_of_coat(&direction, &normal) else {
+            return false;
+        };
+        let path_length = self.path_length(cos_in) + self.path_length(cos_out);
+        *attenuation = *attenuation
+            * self.coat_transmittance(ray_in, path_length)
+            * (1.0 - fresnel_dielectric(cos_out, 1.0 / self.coat_ior));
+        let wavelengths = scattered_ray.wavelengths();
+        *scattered_ray = Ray::from(scattered_ray.origin(), leaving, scattered_ray.time());
+        scattered_ray.set_wavelengths(wavelengths);
+        true
+    }
+
+    fn eval(&self, ray_in: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        let normal = record.normal();
+        let cos_in = dot(&-ray_in.direction().normalize(), &normal).clamp(0.0, 1.0);
+        let cos_out = dot(direction, &normal);
+        if cos_out <= 0.0 {
+            return Color::new();
+        }
+
+        // Evaluate the base between the directions bent into the coating. Refraction squeezes
+        // the directions leaving the base into a wider cone, which spreads out their light.
+        let refracted = self.refracted_ray(ray_in, record);
+        let inside = self.bend_into_coat(direction, &normal);
+        let cos_in_coat = dot(&-refracted.direction().normalize(), &normal);
+        let cos_out_coat = dot(&inside, &normal).max(1e-6);
+        let spreading = cos_out / (self.coat_ior * self.coat_ior * cos_out_coat);
+
+        let path_length = self.path_length(cos_in_coat) + self.path_length(cos_out_coat);
+        self.base.eval(&refracted, record, &inside)
+            * self.coat_transmittance(ray_in, path_length)
+            * ((1.0 - fresnel_dielectric(cos_in, self.coat_ior))
+                * (1.0 - fresnel_dielectric(cos_out, self.coat_ior))
+                * spreading)
+    }
+
+    fn opacity(&self, record: &HitRecord) -> f64 {
+        self.base.opacity(record)
+    }
+}
@@ -35,4 +35,35 @@ pub trait Material: Send + Sync {
         attenuation: &mut Color,
         scattered: &mut Ray,
     ) -> bool;
+
+    /// Evaluates the scattering function times the cosine term for light arriving from `direction`.
+    ///
+    /// This is used to gather light from punctual sources, which rays can never hit by chance.
+    /// Materials that only scatter into discrete directions (mirrors, smooth glass) cannot
+    /// pick up such light and keep the default of black.
+    ///
+    /// Arguments:
+    /// * `ray_in`: A reference to the incoming ray.
+    /// * `record`: A reference to the hit record describing the intersection details.
+    /// * `direction`: Unit vector pointing from the hit point toward the light.
+    ///
+    /// Returns:
+    /// * `Color`: The fraction of incident light reflected back along `ray_in`.
+    fn eval(&self, _ray_in: &Ray, _record: &HitRecord, _direction: &Vec3) -> Color {
+        Color::new()
+    }
+
+    /// Returns how opaque the surface is at a hit, from 0 (a hole) to 1 (solid).
+    ///
+    /// Shapes consult this while intersecting, so rays, including shadow rays, pass through
+    /// transparent parts as if nothing were there. Fractional values are honored stochastically.
+    ///
+    /// Arguments:
+    /// * `record`: A reference to the hit record describing the intersection details.
+    ///
+    /// Returns:
+    /// * `f64`: The probability that a ray stops at this hit.
+    fn opacity(&self, _record: &HitRecord) -> f64 {
+        1.0
+    }
 }
@@ -0,0 +1,179 @@
+use crate::math::onb::Onb;
+use crate::math::rt_math::{random_number, PI};
+use crate::ray::Ray;
+use crate::spectrum;
+use crate::vector::Vec3;
+use crate::Color;
+
+/// What happened to light traveling through a medium.
+pub enum MediumEvent {
+    /// The light scattered at the given distance along the ray.
+    Scattered { distance: f64, weight: Color },
+    /// The light made it all the way through.
+    Passed { weight: Color },
+}
+
+/// A participating medium with the same density everywhere, such as the inside of skin,
+/// wax or milk.
+///
+/// Coefficients are per color channel and in inverse scene units. Because they differ
+/// between channels, distances are sampled for a randomly chosen channel and every channel
+/// is weighted by the average probability over all channels, as in Chiang, Kutz, Burley,
+/// "Practical and Controllable Subsurface Scattering for Production Path Tracing" (2016).
+#[derive(Clone, Copy)]
+pub struct HomogeneousMedium {
+    sigma_a: Color,
+    sigma_s: Color,
+    /// Henyey–Greenstein asymmetry, from back-scattering (-1) to forward-scattering (1).
+    g: f64,
+}
+
+impl HomogeneousMedium {
+    pub fn from(sigma_a: Color, sigma_s: Color, g: f64) -> Self {
+        HomogeneousMedium {
+            sigma_a,
+            sigma_s,
+            g: g.clamp(-0.99, 0.99),
+        }
+    }
+
+    /// Samples how far light travels along `ray` before scattering, up to `max_distance`.
+    ///
+    /// Distances are measured in scene units along the ray's normalized direction.
+    pub fn sample_distance(&self, ray: &Ray, max_distance: f64) -> MediumEvent {
+        let sigma_s = spectrum::uplift(ray, self.sigma_s);
+        let sigma_t = spectrum::uplift(ray, self.sigma_a) + sigma_s;
+
+        let channel = ((random_number() * 3.0) as usize).min(2);
+        let distance = match sigma_t[channel] > 0.0 {
+            true => -(1.0 - random_number()).ln() / sigma_t[channel],
+            _ => f64::INFINITY,
+        };
+
+        let transmittance = |d: f64| {
+            Color::from(
+                (-sigma_t.x() * d).exp(),
+                (-sigma_t.y() * d).exp(),
+                (-sigma_t.z() * d).exp(),
+            )
+        };
+        let average = |c: Color| (c.x() + c.y() + c.z()) / 3.0;
+
+        if distance < max_distance {
+            let density = sigma_t * transmittance(distance);
+            let pdf = average(density);
+            return MediumEvent::Scattered {
+                distance,
+                weight: sigma_s * transmittance(distance) / pdf,
+            };
+        }
+        let surviving = transmittance(max_distance);
+        let pdf = average(surviving);
+        let weight = match pdf > 0.0 {
+            true => surviving / pdf,
+            _ => Color::new(),
+        };
+        MediumEvent::Passed { weight }
+    }
+
+    /// Samples a new direction for light scattering off a particle while traveling along
+    /// the unit vector `direction`.
+    pub fn sample_phase(&self, direction: &Vec3) -> Vec3 {
+        let g = self.g;
+        let u = random_number();
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * u
+        } else {
+            // Measured from the direction of travel, so positive `g` scatters forward
+            let square = (1.0 - g * g) / (1.0 + g - 2.0 * g * u);
+            (1.0 + g * g - square * square) / (2.0 * g)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random_number();
+        let frame = Onb::from_w(direction);
+        frame.world_from_local(&Vec3::from(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::rng;
+    use crate::vector::{dot, Point3};
+
+    const SAMPLES: usize = 200_000;
+
+    fn scattering_cosines(g: f64) -> Vec<f64> {
+        rng::set_stream(0);
+        let medium = HomogeneousMedium::from(Color::new(), Color::from(1.0, 1.0, 1.0), g);
+        let direction = Vec3::from(0.0, 0.6, 0.8);
+        (0..SAMPLES)
+            .map(|_| dot(&medium.sample_phase(&direction), &direction))
+            .collect()
+    }
+
+    #[test]
+    fn phase_mean_cosine_is_g() {
+        for g in [-0.7, -0.2, 0.3, 0.9] {
+            let mean = scattering_cosines(g).iter().sum::<f64>() / SAMPLES as f64;
+            assert!((mean - g).abs() < 0.01, "g = {g}, mean cosine = {mean}");
+        }
+    }
+
+    #[test]
+    fn zero_asymmetry_scatters_isotropically() {
+        // Isotropic directions have their cosines spread evenly over [-1, 1].
+        let mut bins = [0usize; 10];
+        for cos_theta in scattering_cosines(0.0) {
+            bins[(((cos_theta + 1.0) * 5.0) as usize).min(9)] += 1;
+        }
+        let expected = SAMPLES as f64 / 10.0;
+        for count in bins {
+            assert!(
+                (count as f64 - expected).abs() < 0.03 * expected,
+                "{bins:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn zero_albedo_absorbs_everything() {
+        rng::set_stream(0);
+        let medium = HomogeneousMedium::from(Color::from(2.0, 1.0, 0.5), Color::new(), 0.0);
+        let ray = Ray::from(Point3::new(), Vec3::from(1.0, 0.0, 0.0), 0.0);
+        for _ in 0..10_000 {
+            match medium.sample_distance(&ray, 100.0) {
+                MediumEvent::Scattered { weight, .. } => assert_eq!(weight.length(), 0.0),
+                MediumEvent::Passed { .. } => panic!("light passed through 100 mean free paths"),
+            }
+        }
+    }
+
+    #[test]
+    fn passing_weight_averages_to_transmittance() {
+        // Distances are sampled for one channel at a time, so the weights must make up for
+        // the other channels being more or less likely to pass.
+        rng::set_stream(0);
+        let sigma_a = Color::from(0.5, 1.0, 2.0);
+        let medium = HomogeneousMedium::from(sigma_a, Color::from(0.5, 0.5, 0.5), 0.0);
+        let ray = Ray::from(Point3::new(), Vec3::from(1.0, 0.0, 0.0), 0.0);
+        let mut total = Color::new();
+        for _ in 0..SAMPLES {
+            if let MediumEvent::Passed { weight } = medium.sample_distance(&ray, 0.5) {
+                total += weight;
+            }
+        }
+        for channel in 0..3 {
+            let expected = (-(sigma_a[channel] + 0.5) * 0.5).exp();
+            let estimate = total[channel] / SAMPLES as f64;
+            assert!(
+                (estimate - expected).abs() < 0.01,
+                "{estimate} vs {expected}"
+            );
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::materials;
+use crate::spectrum;
 use crate::Color;
 use crate::HitRecord;
 use crate::Ray;
@@ -40,7 +41,7 @@ impl Material for Metal {
             reflected_vector + self.fuzz * Vec3::random_unit_sphere_vector(),
             ray_in.time(),
         );
-        *attenuation = self.albedo;
+        *attenuation = spectrum::uplift(ray_in, self.albedo);
         return true;
     }
 }
@@ -0,0 +1,198 @@
+use crate::math::rt_math::PI;
+use crate::vector::{dot, Vec3};
+
+/// Below this roughness a surface is treated as a perfectly smooth mirror or window.
+const SMOOTH_ALPHA: f64 = 1e-3;
+
+/// The Trowbridge–Reitz (GGX) microfacet distribution with Smith masking-shadowing.
+///
+/// All directions are expressed in the local shading frame, where the surface normal
+/// is the `z` axis. `alpha_x` and `alpha_y` are the roughness along the two tangent axes;
+/// making them differ gives anisotropic highlights such as brushed metal.
+///
+/// Reference: Heitz, "Sampling the GGX Distribution of Visible Normals" (JCGT 2018).
+#[derive(Clone, Copy)]
+pub struct TrowbridgeReitz {
+    alpha_x: f64,
+    alpha_y: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn from(alpha_x: f64, alpha_y: f64) -> Self {
+        TrowbridgeReitz {
+            alpha_x: alpha_x.max(1e-4),
+            alpha_y: alpha_y.max(1e-4),
+        }
+    }
+
+    /// Maps a perceptual roughness in `[0, 1]` to the distribution's alpha parameter.
+    pub fn roughness_to_alpha(roughness: f64) -> f64 {
+        let roughness = roughness.clamp(0.0, 1.0);
+        roughness * roughness
+    }
+
+    /// Returns `true` if the surface is smooth enough to be treated as a perfect specular.
+    pub fn effectively_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < SMOOTH_ALPHA
+    }
+
+    /// Density of microfacets with normal `wm`.
+    pub fn d(&self, wm: &Vec3) -> f64 {
+        let cos2_theta = wm.z() * wm.z();
+        let sin2_theta = (1.0 - cos2_theta).max(0.0);
+        if cos2_theta <= 0.0 {
+            return 0.0;
+        }
+        let tan2_theta = sin2_theta / cos2_theta;
+        let cos4_theta = cos2_theta * cos2_theta;
+        let (cos2_phi, sin2_phi) = cos2_sin2_phi(wm);
+        let e = tan2_theta
+            * (cos2_phi / (self.alpha_x * self.alpha_x) + sin2_phi / (self.alpha_y * self.alpha_y));
+        1.0 / (PI * self.alpha_x * self.alpha_y * cos4_theta * (1.0 + e) * (1.0 + e))
+    }
+
+    /// Smith's auxiliary function, the ratio of hidden to visible microfacet area seen from `w`.
+    pub fn lambda(&self, w: &Vec3) -> f64 {
+        let cos2_theta = w.z() * w.z();
+        if cos2_theta <= 0.0 {
+            return 0.0;
+        }
+        let tan2_theta = (1.0 - cos2_theta).max(0.0) / cos2_theta;
+        let (cos2_phi, sin2_phi) = cos2_sin2_phi(w);
+        let alpha2 =
+            self.alpha_x * self.alpha_x * cos2_phi + self.alpha_y * self.alpha_y * sin2_phi;
+        ((1.0 + alpha2 * tan2_theta).sqrt() - 1.0) / 2.0
+    }
+
+    /// Fraction of microfacets visible from `w`.
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Fraction of microfacets visible from both `wo` and `wi`.
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of microfacet normals `wm` visible from `w`.
+    pub fn visible_d(&self, w: &Vec3, wm: &Vec3) -> f64 {
+        if w.z() == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) / w.z().abs() * self.d(wm) * dot(w, wm).abs()
+    }
+
+    /// Samples a microfacet normal from the distribution of normals visible from `w`.
+    ///
+    /// `u1` and `u2` are uniform random numbers in `[0, 1)`.
+    pub fn sample_wm(&self, w: &Vec3, u1: f64, u2: f64) -> Vec3 {
+        // Transform to the hemispherical configuration.
+        let mut wh = Vec3::from(self.alpha_x * w.x(), self.alpha_y * w.y(), w.z()).normalize();
+        if wh.z() < 0.0 {
+            wh = -wh;
+        }
+
+        // Find an orthonormal basis for the visible normal sampling space.
+        let t1 = if wh.z() < 0.99999 {
+            Vec3::cross(&Vec3::from(0.0, 0.0, 1.0), &wh).normalize()
+        } else {
+            Vec3::from(1.0, 0.0, 0.0)
+        };
+        let t2 = Vec3::cross(&wh, &t1);
+
+        // Sample a uniformly distributed point on the unit disk, then warp it to the
+        // projection of the visible hemisphere.
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let px = r * phi.cos();
+        let mut py = r * phi.sin();
+        let h = (1.0 - px * px).sqrt();
+        let s = (1.0 + wh.z()) / 2.0;
+        py = (1.0 - s) * h + s * py;
+
+        // Reproject onto the hemisphere and transform back to the ellipsoid configuration.
+        let pz = (1.0 - px * px - py * py).max(0.0).sqrt();
+        let nh = px * t1 + py * t2 + pz * wh;
+        Vec3::from(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(1e-6),
+        )
+        .normalize()
+    }
+}
+
+/// Returns `(cos²φ, sin²φ)` for the azimuth of a local-frame direction.
+fn cos2_sin2_phi(w: &Vec3) -> (f64, f64) {
+    let sin2_theta = w.x() * w.x() + w.y() * w.y();
+    if sin2_theta <= 0.0 {
+        return (1.0, 0.0);
+    }
+    (w.x() * w.x() / sin2_theta, w.y() * w.y() / sin2_theta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::rng;
+
+    /// Integrates `f` over the upper hemisphere of directions with the midpoint rule.
+    fn integrate_hemisphere(f: impl Fn(&Vec3) -> f64) -> f64 {
+        let (steps_theta, steps_phi) = (2000, 200);
+        let (d_theta, d_phi) = (PI / 2.0 / steps_theta as f64, 2.0 * PI / steps_phi as f64);
+        let mut total = 0.0;
+        for i in 0..steps_theta {
+            let theta = (i as f64 + 0.5) * d_theta;
+            for j in 0..steps_phi {
+                let phi = (j as f64 + 0.5) * d_phi;
+                let w = Vec3::from(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                );
+                total += f(&w) * theta.sin() * d_theta * d_phi;
+            }
+        }
+        total
+    }
+
+    #[test]
+    fn projected_microfacet_area_is_one() {
+        for (alpha_x, alpha_y) in [(0.3, 0.3), (0.6, 0.6), (0.2, 0.7)] {
+            let distribution = TrowbridgeReitz::from(alpha_x, alpha_y);
+            let area = integrate_hemisphere(|wm| distribution.d(wm) * wm.z());
+            assert!(
+                (area - 1.0).abs() < 1e-3,
+                "alpha ({alpha_x}, {alpha_y}): {area}"
+            );
+        }
+    }
+
+    #[test]
+    fn visible_normals_are_normalized() {
+        let distribution = TrowbridgeReitz::from(0.3, 0.6);
+        let w = Vec3::from(0.6, 0.0, 0.8);
+        // Only microfacets facing `w` are visible; callers discard the others.
+        let total = integrate_hemisphere(|wm| match dot(&w, wm) > 0.0 {
+            true => distribution.visible_d(&w, wm),
+            _ => 0.0,
+        });
+        assert!((total - 1.0).abs() < 1e-3, "{total}");
+    }
+
+    #[test]
+    fn visible_normals_stay_in_upper_hemisphere() {
+        rng::set_stream(0);
+        let distribution = TrowbridgeReitz::from(0.8, 0.1);
+        for _ in 0..100_000 {
+            // Include directions at grazing angles and below the surface.
+            let z = 2.0 * rng::next_f64() - 1.0;
+            let phi = 2.0 * PI * rng::next_f64();
+            let r = (1.0 - z * z).sqrt();
+            let w = Vec3::from(r * phi.cos(), r * phi.sin(), z);
+            let wm = distribution.sample_wm(&w, rng::next_f64(), rng::next_f64());
+            assert!(wm.z() > 0.0, "{wm:?} sampled from {w:?}");
+            assert!((wm.length() - 1.0).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use super::material::Material;
+use crate::math::rt_math::random_number;
+use crate::textures::{solid_color::SolidColor, texture::Texture};
+use crate::Color;
+use crate::HitRecord;
+use crate::Ray;
+use crate::Vec3;
+use std::sync::Arc;
+
+/// Blends two materials, either by a constant weight or by a texture mask.
+///
+/// Each time a ray hits, one of the two materials is picked at random with probability given
+/// by the mask, which on average gives the weighted blend of their appearances.
+pub struct Mix {
+    first: Arc<dyn Material>,
+    second: Arc<dyn Material>,
+    /// How much of `second` to use, in `[0, 1]`.
+    mask: Arc<dyn Texture>,
+}
+
+impl Mix {
+    /// Blends `first` and `second`, using `weight` of the second.
+    pub fn from(first: Arc<dyn Material>, second: Arc<dyn Material>, weight: f64) -> Self {
+        Mix::masked(first, second, Arc::new(SolidColor::from_value(weight)))
+    }
+
+    /// Blends `first` and `second`, reading the amount of the second from a grayscale mask.
+    pub fn masked(
+        first: Arc<dyn Material>,
+        second: Arc<dyn Material>,
+        mask: Arc<dyn Texture>,
+    ) -> Self {
+        Mix {
+            first,
+            second,
+            mask,
+        }
+    }
+
+    fn weight(&self, record: &HitRecord) -> f64 {
+        self.mask
+            .scalar(record.u(), record.v(), &record.point())
+            .clamp(0.0, 1.0)
+    }
+}
+
+impl Material for Mix {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered_ray: &mut Ray,
+    ) -> bool {
+        match random_number() < self.weight(record) {
+            true => self
+                .second
+                .scatter(ray_in, record, attenuation, scattered_ray),
+            _ => self
+                .first
+                .scatter(ray_in, record, attenuation, scattered_ray),
+        }
+    }
+
+    fn eval(&self, ray_in: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        let weight = self.weight(record);
+        (1.0 - weight) * self.first.eval(ray_in, record, direction)
+            + weight * self.second.eval(ray_in, record, direction)
+    }
+
+    fn opacity(&self, record: &HitRecord) -> f64 {
+        let weight = self.weight(record);
+        (1.0 - weight) * self.first.opacity(record) + weight * self.second.opacity(record)
+    }
+}
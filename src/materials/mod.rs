@@ -1,4 +1,18 @@
+pub mod bump;
+pub mod conductor;
+pub mod cutout;
 pub mod dielectric;
+pub mod fresnel;
 pub mod lambert;
+pub mod layered;
 pub mod material;
+pub mod medium;
 pub mod metal;
+pub mod microfacet;
+pub mod mix;
+pub mod oren_nayar;
+pub mod principled;
+pub mod rough_dielectric;
+pub mod sheen;
+pub mod subsurface;
+pub mod thin_film;
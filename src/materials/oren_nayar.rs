@@ -0,0 +1,88 @@
+use super::material::Material;
+use crate::math::onb::Onb;
+use crate::math::rt_math::{degrees_to_radians, PI};
+use crate::spectrum;
+use crate::Color;
+use crate::HitRecord;
+use crate::Ray;
+use crate::Vec3;
+
+/// A rough diffuse surface such as clay, concrete or the moon.
+///
+/// Models the surface as V-shaped Lambertian facets whose slopes have standard deviation
+/// `sigma` (Oren and Nayar, "Generalization of Lambert's Reflectance Model", 1994). Rough
+/// surfaces look flatter than Lambertian ones and brighten toward the light; a `sigma` of
+/// zero is exactly Lambertian.
+pub struct OrenNayar {
+    albedo: Color,
+    a: f64,
+    b: f64,
+}
+
+impl OrenNayar {
+    pub fn new() -> Self {
+        OrenNayar::from(Color::new(), 0.0)
+    }
+
+    /// Creates a rough diffuse material; `sigma` is the facet slope deviation in degrees.
+    pub fn from(albedo: Color, sigma: f64) -> Self {
+        let sigma = degrees_to_radians(sigma);
+        let sigma2 = sigma * sigma;
+        OrenNayar {
+            albedo,
+            a: 1.0 - sigma2 / (2.0 * (sigma2 + 0.33)),
+            b: 0.45 * sigma2 / (sigma2 + 0.09),
+        }
+    }
+
+    /// The factor by which the rough surface differs from a Lambertian one.
+    fn roughness_factor(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        let sin_theta_i = (1.0 - wi.z() * wi.z()).max(0.0).sqrt();
+        let sin_theta_o = (1.0 - wo.z() * wo.z()).max(0.0).sqrt();
+
+        // Cosine of the azimuthal angle between the two directions.
+        let mut max_cos = 0.0;
+        if sin_theta_i > 1e-4 && sin_theta_o > 1e-4 {
+            let cos_delta_phi = (wi.x() * wo.x() + wi.y() * wo.y()) / (sin_theta_i * sin_theta_o);
+            max_cos = cos_delta_phi.max(0.0);
+        }
+
+        let (sin_alpha, tan_beta) = if wi.z().abs() > wo.z().abs() {
+            (sin_theta_o, sin_theta_i / wi.z().abs())
+        } else {
+            (sin_theta_i, sin_theta_o / wo.z().abs())
+        };
+        self.a + self.b * max_cos * sin_alpha * tan_beta
+    }
+}
+
+impl Material for OrenNayar {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered_ray: &mut Ray,
+    ) -> bool {
+        let frame = Onb::from_w(&record.normal());
+        let wo = frame.local_from_world(&-ray_in.direction().normalize());
+        let wi = Vec3::random_cosine_direction();
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return false;
+        }
+        // Cosine-weighted sampling cancels the cosine and 1/π terms.
+        *attenuation = spectrum::uplift(ray_in, self.albedo) * self.roughness_factor(&wo, &wi);
+        *scattered_ray = Ray::from(record.point(), frame.world_from_local(&wi), ray_in.time());
+        true
+    }
+
+    fn eval(&self, ray_in: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        let frame = Onb::from_w(&record.normal());
+        let wo = frame.local_from_world(&-ray_in.direction().normalize());
+        let wi = frame.local_from_world(direction);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::new();
+        }
+        spectrum::uplift(ray_in, self.albedo) * (self.roughness_factor(&wo, &wi) * wi.z() / PI)
+    }
+}
@@ -0,0 +1,378 @@
+use super::fresnel::{fresnel_dielectric, refract};
+use super::material::Material;
+use super::microfacet::TrowbridgeReitz;
+use crate::math::onb::Onb;
+use crate::math::rt_math::{random_number, PI};
+use crate::spectrum;
+use crate::textures::{solid_color::SolidColor, texture::Texture};
+use crate::Color;
+use crate::HitRecord;
+use crate::Ray;
+use crate::Vec3;
+use std::sync::Arc;
+
+/// A single artist-friendly material covering plastics, metals, glass and everything between.
+///
+/// Follows Burley, "Physically-Based Shading at Disney" (2012), with the specular
+/// transmission extension from "Extending the Disney BRDF to a BSDF with Integrated
+/// Subsurface Scattering" (2015). Every parameter is a texture; scalar parameters read the
+/// texture's grayscale value and are expected in `[0, 1]`, except `index_of_refraction`.
+///
+/// Create one with `Principled::new()` and overwrite the fields you need, as with `Camera`.
+pub struct Principled {
+    /// Surface color; the diffuse albedo for dielectrics and the reflectance for metals.
+    pub base_color: Arc<dyn Texture>,
+    /// Blends between a dielectric (0) and a metal (1).
+    pub metallic: Arc<dyn Texture>,
+    /// Microfacet roughness of the specular and transmission lobes.
+    pub roughness: Arc<dyn Texture>,
+    /// Strength of dielectric reflection; 0.5 corresponds to 4% reflectance at normal incidence.
+    pub specular: Arc<dyn Texture>,
+    /// Tints dielectric reflection toward the base color.
+    pub specular_tint: Arc<dyn Texture>,
+    /// Strength of the grazing-angle sheen used for cloth.
+    pub sheen: Arc<dyn Texture>,
+    /// Tints the sheen toward the base color.
+    pub sheen_tint: Arc<dyn Texture>,
+    /// Strength of a second, clear specular layer on top.
+    pub clearcoat: Arc<dyn Texture>,
+    /// Glossiness of the clearcoat layer, from satin (0) to gloss (1).
+    pub clearcoat_gloss: Arc<dyn Texture>,
+    /// Fraction of dielectric light that is transmitted rather than diffusely reflected.
+    pub transmission: Arc<dyn Texture>,
+    /// Index of refraction used for transmission.
+    pub index_of_refraction: Arc<dyn Texture>,
+}
+
+impl Principled {
+    pub fn new() -> Self {
+        Principled {
+            base_color: Arc::new(SolidColor::from(Color::from(0.8, 0.8, 0.8))),
+            metallic: Arc::new(SolidColor::from_value(0.0)),
+            roughness: Arc::new(SolidColor::from_value(0.5)),
+            specular: Arc::new(SolidColor::from_value(0.5)),
+            specular_tint: Arc::new(SolidColor::from_value(0.0)),
+            sheen: Arc::new(SolidColor::from_value(0.0)),
+            sheen_tint: Arc::new(SolidColor::from_value(0.5)),
+            clearcoat: Arc::new(SolidColor::from_value(0.0)),
+            clearcoat_gloss: Arc::new(SolidColor::from_value(1.0)),
+            transmission: Arc::new(SolidColor::from_value(0.0)),
+            index_of_refraction: Arc::new(SolidColor::from_value(1.5)),
+        }
+    }
+
+    /// Looks up every parameter at the hit point.
+    fn lobes(&self, ray_in: &Ray, record: &HitRecord) -> Lobes {
+        let (u, v, point) = (record.u(), record.v(), record.point());
+        let scalar = |texture: &Arc<dyn Texture>| texture.scalar(u, v, &point).clamp(0.0, 1.0);
+
+        let base_color = self.base_color.value(u, v, &point);
+        let metallic = scalar(&self.metallic);
+        let roughness = scalar(&self.roughness);
+        let specular = scalar(&self.specular);
+        let specular_tint = scalar(&self.specular_tint);
+        let sheen_tint = scalar(&self.sheen_tint);
+        let clearcoat_gloss = scalar(&self.clearcoat_gloss);
+        let transmission = scalar(&self.transmission);
+        let index_of_refraction = self.index_of_refraction.scalar(u, v, &point).max(1.0);
+
+        // Tint colors are the base color normalized to unit luminance.
+        let luminance = luminance(&base_color);
+        let tint = match luminance > 0.0 {
+            true => base_color / luminance,
+            _ => Color::from(1.0, 1.0, 1.0),
+        };
+        let white = Color::from(1.0, 1.0, 1.0);
+        let specular_color = lerp(
+            specular * 0.08 * lerp(white, tint, specular_tint),
+            base_color,
+            metallic,
+        );
+
+        let alpha = TrowbridgeReitz::roughness_to_alpha(roughness).max(0.001);
+        let clearcoat_alpha = 0.1 + (0.001 - 0.1) * clearcoat_gloss;
+        Lobes {
+            base_color: spectrum::uplift(ray_in, base_color),
+            specular_color: spectrum::uplift(ray_in, specular_color),
+            sheen_color: spectrum::uplift(
+                ray_in,
+                (1.0 - metallic) * scalar(&self.sheen) * lerp(white, tint, sheen_tint),
+            ),
+            roughness,
+            clearcoat: scalar(&self.clearcoat),
+            clearcoat_alpha,
+            diffuse_weight: (1.0 - metallic) * (1.0 - transmission),
+            transmission_weight: (1.0 - metallic) * transmission,
+            eta: match record.ray_faces_outside() {
+                true => index_of_refraction,
+                _ => 1.0 / index_of_refraction,
+            },
+            from_inside: !record.ray_faces_outside(),
+            distribution: TrowbridgeReitz::from(alpha, alpha),
+        }
+    }
+}
+
+/// The principled parameters evaluated at one hit point, in the local shading frame.
+struct Lobes {
+    base_color: Color,
+    specular_color: Color,
+    sheen_color: Color,
+    roughness: f64,
+    clearcoat: f64,
+    clearcoat_alpha: f64,
+    diffuse_weight: f64,
+    transmission_weight: f64,
+    /// Relative index of refraction from the side the ray arrived on to the other side.
+    eta: f64,
+    /// Set when the ray arrived from inside a transmissive object, where only the
+    /// dielectric interface applies.
+    from_inside: bool,
+    distribution: TrowbridgeReitz,
+}
+
+impl Lobes {
+    /// Probabilities of sampling the diffuse, specular, clearcoat and transmission lobes.
+    fn sampling_weights(&self) -> [f64; 4] {
+        if self.from_inside {
+            return [0.0, 0.0, 0.0, 1.0];
+        }
+        let weights = [
+            self.diffuse_weight,
+            1.0,
+            0.25 * self.clearcoat,
+            self.transmission_weight,
+        ];
+        let total: f64 = weights.iter().sum();
+        weights.map(|weight| weight / total)
+    }
+
+    /// Evaluates the full scattering function times the cosine term.
+    fn f(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return Color::new();
+        }
+        if wi.z() < 0.0 {
+            return self.f_transmission(wo, wi);
+        }
+        let mut wh = *wo + *wi;
+        if wh.approx_zero() {
+            return Color::new();
+        }
+        wh = wh.normalize();
+        let cos_theta_i = wi.z();
+        let cos_theta_o = wo.z();
+        let cos_theta_d = wi.dot(&wh);
+
+        if self.from_inside {
+            // Internal reflection off the dielectric interface.
+            let reflectance = fresnel_dielectric(cos_theta_d, self.eta);
+            let value = self.distribution.d(&wh) * self.distribution.g(wo, wi) * reflectance
+                / (4.0 * cos_theta_o);
+            return Color::from(value, value, value);
+        }
+
+        // Diffuse with retro-reflection at grazing angles, plus sheen.
+        let fl = schlick_weight(cos_theta_i);
+        let fv = schlick_weight(cos_theta_o);
+        let fd90 = 0.5 + 2.0 * cos_theta_d * cos_theta_d * self.roughness;
+        let fd = (1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv);
+        let fh = schlick_weight(cos_theta_d);
+        let diffuse = self.base_color * (fd / PI * self.diffuse_weight) + self.sheen_color * fh;
+
+        // Primary specular, with Schlick Fresnel toward white at grazing angles.
+        let fresnel = lerp(self.specular_color, Color::from(1.0, 1.0, 1.0), fh);
+        let specular = fresnel
+            * (self.distribution.d(&wh) * self.distribution.g(wo, wi)
+                / (4.0 * cos_theta_i * cos_theta_o));
+
+        // Clearcoat: a fixed index of 1.5 and a GTR1 distribution with a long tail.
+        let clearcoat_fresnel = 0.04 + 0.96 * fh;
+        let clearcoat_distribution = TrowbridgeReitz::from(0.25, 0.25);
+        let clearcoat = 0.25
+            * self.clearcoat
+            * clearcoat_fresnel
+            * gtr1(wh.z(), self.clearcoat_alpha)
+            * clearcoat_distribution.g(wo, wi)
+            / (4.0 * cos_theta_i * cos_theta_o);
+
+        (diffuse + specular + Color::from(clearcoat, clearcoat, clearcoat)) * cos_theta_i
+    }
+
+    /// Evaluates rough dielectric transmission (Walter et al. 2007) times the cosine term.
+    fn f_transmission(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        let weight = match self.from_inside {
+            true => 1.0,
+            _ => self.transmission_weight,
+        };
+        if weight <= 0.0 {
+            return Color::new();
+        }
+        let mut wh = *wi * self.eta + *wo;
+        if wh.approx_zero() {
+            return Color::new();
+        }
+        wh = wh.normalize();
+        if wh.z() < 0.0 {
+            wh = -wh;
+        }
+        let wi_dot_h = wi.dot(&wh);
+        let wo_dot_h = wo.dot(&wh);
+        if wi_dot_h * wi.z() < 0.0 || wo_dot_h < 0.0 {
+            return Color::new();
+        }
+        let denominator = wi_dot_h + wo_dot_h / self.eta;
+        let denominator = denominator * denominator * wo.z();
+        let value = weight
+            * (1.0 - fresnel_dielectric(wo_dot_h, self.eta))
+            * self.distribution.d(&wh)
+            * self.distribution.g(wo, wi)
+            * (wi_dot_h * wo_dot_h / denominator).abs()
+            / (self.eta * self.eta);
+        // Light entering the object takes on the base color; light leaving it does not.
+        match self.from_inside {
+            true => Color::from(value, value, value),
+            _ => self.base_color * value,
+        }
+    }
+
+    /// Probability density of sampling `wi` with `sample`.
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        let [diffuse, specular, clearcoat, transmission] = self.sampling_weights();
+        if wi.z() < 0.0 {
+            let mut wh = *wi * self.eta + *wo;
+            if wh.approx_zero() {
+                return 0.0;
+            }
+            wh = wh.normalize();
+            if wh.z() < 0.0 {
+                wh = -wh;
+            }
+            let denominator = wi.dot(&wh) + wo.dot(&wh) / self.eta;
+            let jacobian = wi.dot(&wh).abs() / (denominator * denominator);
+            let refract_probability = 1.0 - fresnel_dielectric(wo.dot(&wh), self.eta);
+            return transmission
+                * refract_probability
+                * self.distribution.visible_d(wo, &wh)
+                * jacobian;
+        }
+
+        let wh = (*wo + *wi).normalize();
+        let wo_dot_h = wo.dot(&wh).abs();
+        let mut pdf = diffuse * wi.z() / PI
+            + specular * self.distribution.visible_d(wo, &wh) / (4.0 * wo_dot_h)
+            + clearcoat * gtr1(wh.z(), self.clearcoat_alpha) * wh.z() / (4.0 * wo_dot_h);
+        // The transmission lobe reflects whatever it fails to refract.
+        let reflect_probability = fresnel_dielectric(wo.dot(&wh), self.eta);
+        pdf += transmission * reflect_probability * self.distribution.visible_d(wo, &wh)
+            / (4.0 * wo_dot_h);
+        pdf
+    }
+
+    /// Picks a lobe and samples an incident direction from it.
+    ///
+    /// Only refraction may leave through the lower hemisphere; reflected directions that end up
+    /// below the surface are rejected, since `f` and `pdf` would score them as transmission.
+    fn sample(&self, wo: &Vec3) -> Option<Vec3> {
+        let [diffuse, specular, clearcoat, _] = self.sampling_weights();
+        let choice = random_number();
+        let wi = if choice < diffuse {
+            Vec3::random_cosine_direction()
+        } else if choice < diffuse + specular {
+            let wh = self
+                .distribution
+                .sample_wm(wo, random_number(), random_number());
+            Vec3::reflect(&-*wo, &wh)
+        } else if choice < diffuse + specular + clearcoat {
+            Vec3::reflect(&-*wo, &sample_gtr1(self.clearcoat_alpha))
+        } else {
+            let wh = self
+                .distribution
+                .sample_wm(wo, random_number(), random_number());
+            if random_number() >= fresnel_dielectric(wo.dot(&wh), self.eta) {
+                let wi = refract(wo, &wh, self.eta)?;
+                return match wi.z() < 0.0 {
+                    true => Some(wi),
+                    _ => None,
+                };
+            }
+            Vec3::reflect(&-*wo, &wh)
+        };
+        match wi.z() > 0.0 {
+            true => Some(wi),
+            _ => None,
+        }
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered_ray: &mut Ray,
+    ) -> bool {
+        let lobes = self.lobes(ray_in, record);
+        let frame = Onb::from_w(&record.normal());
+        let wo = frame.local_from_world(&-ray_in.direction().normalize());
+        if wo.z() <= 0.0 {
+            return false;
+        }
+        let wi = match lobes.sample(&wo) {
+            Some(wi) => wi,
+            None => return false,
+        };
+        let pdf = lobes.pdf(&wo, &wi);
+        if pdf <= 0.0 {
+            return false;
+        }
+        *attenuation = lobes.f(&wo, &wi) / pdf;
+        *scattered_ray = Ray::from(record.point(), frame.world_from_local(&wi), ray_in.time());
+        true
+    }
+
+    fn eval(&self, ray_in: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        let lobes = self.lobes(ray_in, record);
+        let frame = Onb::from_w(&record.normal());
+        let wo = frame.local_from_world(&-ray_in.direction().normalize());
+        let wi = frame.local_from_world(direction);
+        lobes.f(&wo, &wi)
+    }
+}
+
+/// `(1 - cos θ)⁵`, the angular falloff in Schlick's Fresnel approximation.
+fn schlick_weight(cos_theta: f64) -> f64 {
+    let m = (1.0 - cos_theta).clamp(0.0, 1.0);
+    let m2 = m * m;
+    m2 * m2 * m
+}
+
+/// The Generalized Trowbridge–Reitz distribution with `γ = 1`, used by the clearcoat.
+fn gtr1(cos_theta_h: f64, alpha: f64) -> f64 {
+    if alpha >= 1.0 {
+        return 1.0 / PI;
+    }
+    let alpha2 = alpha * alpha;
+    let t = 1.0 + (alpha2 - 1.0) * cos_theta_h * cos_theta_h;
+    (alpha2 - 1.0) / (PI * alpha2.ln() * t)
+}
+
+/// Samples a half vector proportionally to `gtr1(cos θ) cos θ`.
+fn sample_gtr1(alpha: f64) -> Vec3 {
+    let alpha2 = alpha * alpha;
+    let cos_theta = ((1.0 - alpha2.powf(1.0 - random_number())) / (1.0 - alpha2))
+        .max(0.0)
+        .sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * random_number();
+    Vec3::from(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+fn luminance(color: &Color) -> f64 {
+    0.3 * color.x() + 0.6 * color.y() + 0.1 * color.z()
+}
+
+fn lerp(a: Color, b: Color, t: f64) -> Color {
+    (1.0 - t) * a + t * b
+}
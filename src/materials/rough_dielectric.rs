@@ -0,0 +1,195 @@
+use super::fresnel::{fresnel_dielectric, refract};
+use super::material::Material;
+use super::microfacet::TrowbridgeReitz;
+use crate::math::onb::Onb;
+use crate::math::rt_math::random_number;
+use crate::textures::{solid_color::SolidColor, texture::Texture};
+use crate::Color;
+use crate::HitRecord;
+use crate::Ray;
+use crate::Vec3;
+use std::sync::Arc;
+
+/// A dielectric with a microscopically rough surface, such as frosted glass or ice.
+///
+/// Light is reflected and refracted by GGX microfacets following Walter et al.,
+/// "Microfacet Models for Refraction through Rough Surfaces" (2007), with the choice
+/// between reflection and refraction made by the exact Fresnel equations.
+pub struct RoughDielectric {
+    index_of_refraction: f64,
+    roughness: Arc<dyn Texture>,
+}
+
+impl RoughDielectric {
+    pub fn new() -> Self {
+        RoughDielectric {
+            index_of_refraction: 1.5,
+            roughness: Arc::new(SolidColor::from_value(0.0)),
+        }
+    }
+
+    /// Creates a rough dielectric whose perceptual roughness in `[0, 1]` is read from a texture.
+    pub fn from(index_of_refraction: f64, roughness: Arc<dyn Texture>) -> Self {
+        RoughDielectric {
+            index_of_refraction,
+            roughness,
+        }
+    }
+
+    /// Builds the microfacet distribution at the hit point.
+    fn distribution(&self, record: &HitRecord) -> TrowbridgeReitz {
+        let roughness = self
+            .roughness
+            .scalar(record.u(), record.v(), &record.point());
+        let alpha = TrowbridgeReitz::roughness_to_alpha(roughness);
+        TrowbridgeReitz::from(alpha, alpha)
+    }
+
+    /// Relative index of refraction from the side the ray arrived on to the other side.
+    fn relative_ior(&self, record: &HitRecord) -> f64 {
+        match record.ray_faces_outside() {
+            true => self.index_of_refraction,
+            _ => 1.0 / self.index_of_refraction,
+        }
+    }
+
+    /// Sets up the shading frame at the hit point for sampling or evaluating the surface.
+    ///
+    /// Returns `None` if the ray arrives from below the shading normal.
+    fn local_geometry(&self, ray_in: &Ray, record: &HitRecord) -> Option<LocalGeometry> {
+        let frame = Onb::from_w(&record.normal());
+        let wo = frame.local_from_world(&-ray_in.direction().normalize());
+        if wo.z() <= 0.0 {
+            return None;
+        }
+        Some(LocalGeometry {
+            frame,
+            wo,
+            eta: self.relative_ior(record),
+            distribution: self.distribution(record),
+        })
+    }
+}
+
+/// The shading frame at a hit point and what both sampling and evaluation derive from it.
+struct LocalGeometry {
+    frame: Onb,
+    /// The direction back along the incoming ray, in the shading frame.
+    wo: Vec3,
+    /// Relative index of refraction from the side of `wo` to the other side.
+    eta: f64,
+    distribution: TrowbridgeReitz,
+}
+
+/// Returns the microfacet normal that reflects `wo` into `wi`, or refracts one into the
+/// other, on the upper side of the surface.
+///
+/// Returns `None` if there is no such normal or the microfacet faces away from either
+/// direction.
+fn half_vector(wo: &Vec3, wi: &Vec3, eta: f64) -> Option<Vec3> {
+    let eta = match wi.z() > 0.0 {
+        true => 1.0,
+        _ => eta,
+    };
+    let wm = *wi * eta + *wo;
+    if wm.approx_zero() {
+        return None;
+    }
+    let mut wm = wm.normalize();
+    if wm.z() < 0.0 {
+        wm = -wm;
+    }
+    // Discard back-facing microfacets.
+    if wm.dot(wi) * wi.z() < 0.0 || wm.dot(wo) * wo.z() < 0.0 {
+        return None;
+    }
+    Some(wm)
+}
+
+impl Material for RoughDielectric {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered_ray: &mut Ray,
+    ) -> bool {
+        let Some(LocalGeometry {
+            frame,
+            wo,
+            eta,
+            distribution,
+        }) = self.local_geometry(ray_in, record)
+        else {
+            return false;
+        };
+
+        let wm = if distribution.effectively_smooth() {
+            Vec3::from(0.0, 0.0, 1.0)
+        } else {
+            distribution.sample_wm(&wo, random_number(), random_number())
+        };
+        let reflectance = fresnel_dielectric(wo.dot(&wm), eta);
+
+        // Pick reflection or refraction in proportion to the Fresnel terms, which cancel
+        // against the selection probability and leave only the shadowing ratio. Like the
+        // smooth `Dielectric`, transmission does not rescale radiance by the change in solid
+        // angle, which cancels out for light that leaves the object again.
+        let wi = if random_number() < reflectance {
+            let wi = Vec3::reflect(&-wo, &wm);
+            if wi.z() <= 0.0 {
+                return false;
+            }
+            wi
+        } else {
+            match refract(&wo, &wm, eta) {
+                Some(wi) if wi.z() < 0.0 => wi,
+                _ => return false,
+            }
+        };
+
+        let shadowing = match distribution.effectively_smooth() {
+            true => 1.0,
+            _ => distribution.g(&wo, &wi) / distribution.g1(&wo),
+        };
+        *attenuation = Color::from(1.0, 1.0, 1.0) * shadowing;
+        *scattered_ray = Ray::from(record.point(), frame.world_from_local(&wi), ray_in.time());
+        true
+    }
+
+    fn eval(&self, ray_in: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        let Some(LocalGeometry {
+            frame,
+            wo,
+            eta,
+            distribution,
+        }) = self.local_geometry(ray_in, record)
+        else {
+            return Color::new();
+        };
+        if distribution.effectively_smooth() {
+            return Color::new();
+        }
+        let wi = frame.local_from_world(direction);
+        let (cos_theta_o, cos_theta_i) = (wo.z(), wi.z());
+        if cos_theta_i == 0.0 {
+            return Color::new();
+        }
+        let Some(wm) = half_vector(&wo, &wi, eta) else {
+            return Color::new();
+        };
+
+        let reflectance = fresnel_dielectric(wo.dot(&wm), eta);
+        let value = if cos_theta_i > 0.0 {
+            distribution.d(&wm) * distribution.g(&wo, &wi) * reflectance / (4.0 * cos_theta_o)
+        } else {
+            let denominator = wi.dot(&wm) + wo.dot(&wm) / eta;
+            let denominator = denominator * denominator * cos_theta_o;
+            distribution.d(&wm)
+                * (1.0 - reflectance)
+                * distribution.g(&wo, &wi)
+                * (wi.dot(&wm) * wo.dot(&wm) / denominator).abs()
+        };
+        Color::from(value, value, value)
+    }
+}
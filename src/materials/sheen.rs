@@ -0,0 +1,118 @@
+use super::material::Material;
+use crate::math::onb::Onb;
+use crate::math::rt_math::{random_number, PI};
+use crate::spectrum;
+use crate::Color;
+use crate::HitRecord;
+use crate::Ray;
+use crate::Vec3;
+
+/// A fabric material: a diffuse base under a soft sheen that brightens toward grazing angles,
+/// as on velvet, satin and felt.
+///
+/// The sheen uses the "Charlie" microfiber distribution from Estevez and Kulla, "Production
+/// Friendly Microfacet Sheen BRDF" (2017), with the visibility term from Neubelt and
+/// Pettineo, "Crafting a Next-Gen Material Pipeline for The Order: 1886" (2013). Both lobes
+/// are importance sampled.
+pub struct Sheen {
+    base: Color,
+    sheen_color: Color,
+    alpha: f64,
+}
+
+impl Sheen {
+    pub fn new() -> Self {
+        Sheen::from(Color::new(), Color::from(1.0, 1.0, 1.0), 0.5)
+    }
+
+    /// Creates a fabric with diffuse color `base` and a sheen of `sheen_color` whose
+    /// `roughness` in `[0, 1]` controls how tightly it hugs the silhouette.
+    pub fn from(base: Color, sheen_color: Color, roughness: f64) -> Self {
+        let roughness = roughness.clamp(0.0, 1.0);
+        Sheen {
+            base,
+            sheen_color,
+            alpha: (roughness * roughness).max(1e-3),
+        }
+    }
+
+    /// Density of fibers oriented along `wh`.
+    fn d(&self, wh: &Vec3) -> f64 {
+        let sin_theta = (1.0 - wh.z() * wh.z()).max(0.0).sqrt();
+        (2.0 + 1.0 / self.alpha) * sin_theta.powf(1.0 / self.alpha) / (2.0 * PI)
+    }
+
+    /// Probability of choosing the sheen lobe over the diffuse lobe when sampling.
+    fn sheen_probability(&self) -> f64 {
+        let sheen = self.sheen_color.max_component();
+        let base = self.base.max_component();
+        match sheen + base > 0.0 {
+            true => sheen / (sheen + base),
+            _ => 0.5,
+        }
+    }
+
+    /// Evaluates both lobes times the cosine term.
+    fn f(&self, ray_in: &Ray, wo: &Vec3, wi: &Vec3) -> Color {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::new();
+        }
+        let wh = (*wo + *wi).normalize();
+        let visibility = 1.0 / (4.0 * (wi.z() + wo.z() - wi.z() * wo.z()));
+        let diffuse = spectrum::uplift(ray_in, self.base) / PI;
+        let sheen = spectrum::uplift(ray_in, self.sheen_color) * (self.d(&wh) * visibility);
+        (diffuse + sheen) * wi.z()
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        if wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let wh = (*wo + *wi).normalize();
+        let sheen_pdf = self.d(&wh) * wh.z() / (4.0 * wo.dot(&wh).abs());
+        let p = self.sheen_probability();
+        p * sheen_pdf + (1.0 - p) * wi.z() / PI
+    }
+
+    /// Samples a fiber direction proportionally to `d(wh) cos θh`.
+    fn sample_wh(&self) -> Vec3 {
+        let sin_theta = random_number().powf(self.alpha / (2.0 * self.alpha + 1.0));
+        let cos_theta = (1.0 - sin_theta * sin_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random_number();
+        Vec3::from(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+    }
+}
+
+impl Material for Sheen {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered_ray: &mut Ray,
+    ) -> bool {
+        let frame = Onb::from_w(&record.normal());
+        let wo = frame.local_from_world(&-ray_in.direction().normalize());
+        if wo.z() <= 0.0 {
+            return false;
+        }
+        let wi = match random_number() < self.sheen_probability() {
+            true => Vec3::reflect(&-wo, &self.sample_wh()),
+            _ => Vec3::random_cosine_direction(),
+        };
+        let pdf = self.pdf(&wo, &wi);
+        if pdf <= 0.0 {
+            return false;
+        }
+        *attenuation = self.f(ray_in, &wo, &wi) / pdf;
+        *scattered_ray = Ray::from(record.point(), frame.world_from_local(&wi), ray_in.time());
+        true
+    }
+
+    fn eval(&self, ray_in: &Ray, record: &HitRecord, direction: &Vec3) -> Color {
+        let frame = Onb::from_w(&record.normal());
+        let wo = frame.local_from_world(&-ray_in.direction().normalize());
+        let wi = frame.local_from_world(direction);
+        self.f(ray_in, &wo, &wi)
+    }
+}
@@ -0,0 +1,137 @@
+use super::fresnel::fresnel_dielectric;
+use super::material::Material;
+use super::medium::{HomogeneousMedium, MediumEvent};
+use crate::math::rt_math::random_number;
+use crate::perspective_camera::camera::RAY_EPSILON;
+use crate::vector::{dot, Point3};
+use crate::Color;
+use crate::HitRecord;
+use crate::Ray;
+use crate::Vec3;
+
+/// Closest a boundary can be to a point on the walk and still be found.
+const WALK_EPSILON: f64 = 0.001;
+
+/// How far behind their starting point walk rays begin, so that the camera's `RAY_EPSILON`
+/// only skips the first `WALK_EPSILON` of the walk.
+const HEAD_START: f64 = RAY_EPSILON - WALK_EPSILON;
+
+/// A translucent material such as skin, wax, marble or milk, rendered by following light on a
+/// random walk through the object's interior.
+///
+/// The surface is a smooth dielectric boundary. Light that enters travels through a
+/// `HomogeneousMedium`, scattering off particles until it finds its way back out, so the
+/// object must be closed. Each scattering event counts as a bounce, so dense materials need
+/// a generous `Camera::max_depth`.
+pub struct Subsurface {
+    index_of_refraction: f64,
+    medium: HomogeneousMedium,
+}
+
+impl Subsurface {
+    pub fn new() -> Self {
+        Subsurface::from(Color::from(0.8, 0.8, 0.8), Color::from(0.1, 0.1, 0.1), 1.4)
+    }
+
+    /// Creates a subsurface material.
+    ///
+    /// * `albedo`: The overall color the surface takes on once light has bounced around inside.
+    /// * `mean_free_path`: Average distance light travels between scattering events, per
+    ///   channel, in scene units. Larger values let light bleed further through the object.
+    /// * `index_of_refraction`: Index of refraction of the boundary.
+    pub fn from(albedo: Color, mean_free_path: Color, index_of_refraction: f64) -> Self {
+        Subsurface::anisotropic(albedo, mean_free_path, index_of_refraction, 0.0)
+    }
+
+    /// Creates a subsurface material whose particles scatter light preferentially forward
+    /// (`g > 0`) or backward (`g < 0`).
+    pub fn anisotropic(
+        albedo: Color,
+        mean_free_path: Color,
+        index_of_refraction: f64,
+        g: f64,
+    ) -> Self {
+        let extinction = |mean_free_path: f64| 1.0 / mean_free_path.max(1e-6);
+        let sigma_t = Color::from(
+            extinction(mean_free_path.x()),
+            extinction(mean_free_path.y()),
+            extinction(mean_free_path.z()),
+        );
+        let single_scattering = Color::from(
+            single_scattering_albedo(albedo.x()),
+            single_scattering_albedo(albedo.y()),
+            single_scattering_albedo(albedo.z()),
+        );
+        let sigma_s = sigma_t * single_scattering;
+        Subsurface {
+            index_of_refraction,
+            medium: HomogeneousMedium::from(sigma_t - sigma_s, sigma_s, g),
+        }
+    }
+}
+
+/// Inverts the relation between the single-scattering albedo of the medium and the albedo
+/// of the surface after many bounces, so `albedo` can be specified as the color you see.
+///
+/// Uses the fit from Chiang, Kutz, Burley (2016).
+fn single_scattering_albedo(albedo: f64) -> f64 {
+    let albedo = albedo.clamp(0.0, 0.999);
+    let s = 4.09712 + 4.20863 * albedo
+        - (9.59217 + 41.6808 * albedo + 17.7126 * albedo * albedo).sqrt();
+    1.0 - s * s
+}
+
+impl Material for Subsurface {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered_ray: &mut Ray,
+    ) -> bool {
+        let unit_direction: Vec3 = ray_in.direction().normalize();
+        *attenuation = Color::from(1.0, 1.0, 1.0);
+
+        if !record.ray_faces_outside() {
+            // The ray has been traveling through the interior; see whether it scattered
+            // before reaching the boundary.
+            // Walk rays start `HEAD_START` behind the point they left, which is not part of the
+            // walk.
+            let distance = (record.parameter() * ray_in.direction().length() - HEAD_START).max(0.0);
+            match self.medium.sample_distance(ray_in, distance) {
+                MediumEvent::Scattered { distance, weight } => {
+                    let origin = ray_in.origin() + (HEAD_START + distance) * unit_direction;
+                    let direction = self.medium.sample_phase(&unit_direction);
+                    *attenuation = weight;
+                    *scattered_ray = walk_ray(origin, direction, ray_in.time());
+                    return true;
+                }
+                MediumEvent::Passed { weight } => *attenuation = weight,
+            }
+        }
+
+        // Reflect off or pass through the smooth boundary.
+        let refraction_ratio: f64 = match record.ray_faces_outside() {
+            true => 1.0 / self.index_of_refraction,
+            _ => self.index_of_refraction,
+        };
+        let cos_theta = f64::min(dot(&-unit_direction, &record.normal()), 1.0);
+        let direction = if random_number() < fresnel_dielectric(cos_theta, 1.0 / refraction_ratio) {
+            Vec3::reflect(&unit_direction, &record.normal())
+        } else {
+            Vec3::refract(&unit_direction, &record.normal(), refraction_ratio)
+        };
+        *scattered_ray = walk_ray(record.point(), direction, ray_in.time());
+        true
+    }
+}
+
+/// Starts a ray `HEAD_START` behind `origin`, so that it finds anything more than
+/// `WALK_EPSILON` past `origin`.
+///
+/// Scattering events can land within `RAY_EPSILON` of the boundary of small or thin objects,
+/// and a walk that ignored hits that close would step straight out through it.
+fn walk_ray(origin: Point3, direction: Vec3, time: f64) -> Ray {
+    let direction = direction.normalize();
+    Ray::from(origin - HEAD_START * direction, direction, time)
+}
@@ -0,0 +1,183 @@
+use super::fresnel::fresnel_thin_film;
+use super::material::Material;
+use crate::math::rt_math::random_number;
+use crate::spectrum::{self, rgb_to_spectrum};
+use crate::textures::{solid_color::SolidColor, texture::Texture};
+use crate::vector::dot;
+use crate::Color;
+use crate::HitRecord;
+use crate::Ray;
+use crate::Vec3;
+use std::sync::Arc;
+
+/// The smooth material a thin film is coated onto.
+#[derive(Clone, Copy)]
+pub enum FilmBase {
+    /// A transparent base such as glass or water. An index of `1.0` gives a free-standing
+    /// film like a soap bubble.
+    Dielectric { index_of_refraction: f64 },
+    /// An opaque metal with complex index of refraction `eta + ik`, per channel.
+    Conductor { eta: Color, k: Color },
+}
+
+/// A smooth surface with a thin transparent coating, as on soap bubbles, oil slicks and
+/// anti-reflective lenses.
+///
+/// Light reflected from the top and bottom of the film interferes, so the reflectance depends
+/// on wavelength, film thickness and viewing angle, producing iridescent colors. In spectral
+/// mode the reflectance is evaluated at the path's wavelengths; in RGB mode it is integrated
+/// over the visible spectrum.
+pub struct ThinFilm {
+    base: FilmBase,
+    film_ior: f64,
+    /// Film thickness in nanometers.
+    thickness: Arc<dyn Texture>,
+}
+
+impl ThinFilm {
+    pub fn new() -> Self {
+        ThinFilm::soap_bubble(Arc::new(SolidColor::from_value(400.0)))
+    }
+
+    /// Coats a smooth dielectric with a film whose thickness in nanometers is read from a texture.
+    pub fn over_dielectric(
+        index_of_refraction: f64,
+        film_ior: f64,
+        thickness: Arc<dyn Texture>,
+    ) -> Self {
+        ThinFilm {
+            base: FilmBase::Dielectric {
+                index_of_refraction,
+            },
+            film_ior,
+            thickness,
+        }
+    }
+
+    /// Coats a smooth metal with a film whose thickness in nanometers is read from a texture.
+    pub fn over_conductor(
+        eta: Color,
+        k: Color,
+        film_ior: f64,
+        thickness: Arc<dyn Texture>,
+    ) -> Self {
+        ThinFilm {
+            base: FilmBase::Conductor { eta, k },
+            film_ior,
+            thickness,
+        }
+    }
+
+    /// A free-standing film of soapy water surrounded by air.
+    pub fn soap_bubble(thickness: Arc<dyn Texture>) -> Self {
+        ThinFilm::over_dielectric(1.0, 1.33, thickness)
+    }
+
+    /// Film reflectance at one wavelength, for light arriving with the given cosine.
+    fn reflectance_at(
+        &self,
+        cos_theta: f64,
+        faces_outside: bool,
+        thickness: f64,
+        wavelength: f64,
+    ) -> f64 {
+        match self.base {
+            FilmBase::Dielectric {
+                index_of_refraction,
+            } => {
+                // From inside the base the light crosses base, film, then air.
+                let (outer_ior, base_ior) = match faces_outside {
+                    true => (1.0, index_of_refraction),
+                    _ => (index_of_refraction, 1.0),
+                };
+                fresnel_thin_film(
+                    cos_theta,
+                    outer_ior,
+                    self.film_ior,
+                    base_ior,
+                    0.0,
+                    thickness,
+                    wavelength,
+                )
+            }
+            FilmBase::Conductor { eta, k } => fresnel_thin_film(
+                cos_theta,
+                1.0,
+                self.film_ior,
+                rgb_to_spectrum(eta, wavelength),
+                rgb_to_spectrum(k, wavelength),
+                thickness,
+                wavelength,
+            ),
+        }
+    }
+
+    /// Film reflectance for each channel of the ray's color.
+    fn reflectance(&self, ray_in: &Ray, record: &HitRecord, cos_theta: f64) -> Color {
+        let thickness = self
+            .thickness
+            .scalar(record.u(), record.v(), &record.point());
+        let faces_outside = record.ray_faces_outside();
+        match ray_in.wavelengths() {
+            Some(wavelengths) => Color::from(
+                self.reflectance_at(cos_theta, faces_outside, thickness, wavelengths.get(0)),
+                self.reflectance_at(cos_theta, faces_outside, thickness, wavelengths.get(1)),
+                self.reflectance_at(cos_theta, faces_outside, thickness, wavelengths.get(2)),
+            ),
+            None => spectrum::reflectance_to_rgb(|wavelength| {
+                self.reflectance_at(cos_theta, faces_outside, thickness, wavelength)
+            }),
+        }
+    }
+}
+
+impl Material for ThinFilm {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        attenuation: &mut Color,
+        scattered_ray: &mut Ray,
+    ) -> bool {
+        let unit_direction: Vec3 = ray_in.direction().normalize();
+        let cos_theta = f64::min(dot(&-unit_direction, &record.normal()), 1.0);
+        let reflectance = self
+            .reflectance(ray_in, record, cos_theta)
+            .clamp_components(0.0, 1.0);
+        let reflected = Vec3::reflect(&unit_direction, &record.normal());
+
+        let index_of_refraction = match self.base {
+            FilmBase::Conductor { .. } => {
+                *attenuation = reflectance;
+                *scattered_ray = Ray::from(record.point(), reflected, ray_in.time());
+                return true;
+            }
+            FilmBase::Dielectric {
+                index_of_refraction,
+            } => index_of_refraction,
+        };
+
+        // The film is parallel to the surface, so refracted light leaves it in the direction
+        // Snell's law gives for the base alone.
+        let refraction_ratio = match record.ray_faces_outside() {
+            true => 1.0 / index_of_refraction,
+            _ => index_of_refraction,
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let reflect_probability = match refraction_ratio * sin_theta > 1.0 {
+            true => 1.0,
+            _ => (reflectance.x() + reflectance.y() + reflectance.z()) / 3.0,
+        };
+
+        if random_number() < reflect_probability {
+            *attenuation = reflectance / reflect_probability;
+            *scattered_ray = Ray::from(record.point(), reflected, ray_in.time());
+        } else {
+            let transmittance = Color::from(1.0, 1.0, 1.0) - reflectance;
+            *attenuation = transmittance / (1.0 - reflect_probability);
+            let refracted = Vec3::refract(&unit_direction, &record.normal(), refraction_ratio);
+            *scattered_ray = Ray::from(record.point(), refracted, ray_in.time());
+        }
+        true
+    }
+}
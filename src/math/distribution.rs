@@ -0,0 +1,187 @@
+/// A piecewise-constant probability distribution over `[0, 1)`, built from a tabulated function.
+///
+/// Used to importance sample things like aperture images, so samples land where the function is
+/// large. Follows the `Distribution1D` of PBRT.
+pub struct Distribution1D {
+    function: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D {
+    /// Builds a distribution proportional to the non-negative values in `function`.
+    ///
+    /// A function that is zero everywhere gives the uniform distribution.
+    pub fn from(function: Vec<f64>) -> Self {
+        let count = function.len();
+        let mut cdf = vec![0.0; count + 1];
+        for i in 1..=count {
+            cdf[i] = cdf[i - 1] + function[i - 1].max(0.0) / count as f64;
+        }
+        let integral = cdf[count];
+        for (i, value) in cdf.iter_mut().enumerate() {
+            *value = match integral > 0.0 {
+                true => *value / integral,
+                _ => i as f64 / count as f64,
+            };
+        }
+        Distribution1D {
+            function,
+            cdf,
+            integral,
+        }
+    }
+
+    /// Returns the number of pieces.
+    pub fn count(&self) -> usize {
+        self.function.len()
+    }
+
+    /// Returns the integral of the function over `[0, 1)`.
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Maps a uniform random number `u` to a sample, returning it with its probability density.
+    pub fn sample(&self, u: f64) -> (f64, f64) {
+        let count = self.count();
+        let offset = (self.cdf.partition_point(|&value| value <= u).max(1) - 1).min(count - 1);
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        let within = match width > 0.0 {
+            true => (u - self.cdf[offset]) / width,
+            _ => 0.0,
+        };
+        let x = ((offset as f64 + within) / count as f64).min(1.0 - f64::EPSILON);
+        (x, self.pdf(x))
+    }
+
+    /// Returns the probability density of sampling `x`.
+    pub fn pdf(&self, x: f64) -> f64 {
+        if self.integral <= 0.0 {
+            return 1.0;
+        }
+        let offset = ((x * self.count() as f64) as usize).min(self.count() - 1);
+        self.function[offset].max(0.0) / self.integral
+    }
+}
+
+/// A piecewise-constant probability distribution over the unit square, built from a grid.
+///
+/// Samples pick a row from the marginal distribution and then a column within that row.
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /// Builds a distribution proportional to `function`, a `width * height` grid stored row by
+    /// row. The first row is `y = 0`.
+    pub fn from(width: usize, height: usize, function: &[f64]) -> Self {
+        assert_eq!(function.len(), width * height, "grid size must match");
+        let conditional: Vec<Distribution1D> = function
+            .chunks_exact(width)
+            .map(|row| Distribution1D::from(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::from(conditional.iter().map(|row| row.integral()).collect());
+        Distribution2D {
+            conditional,
+            marginal,
+        }
+    }
+
+    /// Returns the integral of the function over the unit square.
+    pub fn integral(&self) -> f64 {
+        self.marginal.integral()
+    }
+
+    /// Maps two uniform random numbers to a point in the unit square, returning it with its
+    /// probability density.
+    pub fn sample(&self, u: f64, v: f64) -> ((f64, f64), f64) {
+        let (y, marginal_pdf) = self.marginal.sample(v);
+        let row = ((y * self.marginal.count() as f64) as usize).min(self.marginal.count() - 1);
+        let (x, conditional_pdf) = self.conditional[row].sample(u);
+        ((x, y), marginal_pdf * conditional_pdf)
+    }
+
+    /// Returns the probability density of sampling the point `(x, y)`.
+    pub fn pdf(&self, x: f64, y: f64) -> f64 {
+        let row = ((y * self.marginal.count() as f64) as usize).min(self.marginal.count() - 1);
+        self.marginal.pdf(y) * self.conditional[row].pdf(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_land_in_proportion_to_the_function() {
+        let distribution = Distribution1D::from(vec![1.0, 0.0, 3.0, 4.0]);
+        let mut counts = [0; 4];
+        let n = 8000;
+        for i in 0..n {
+            let (x, _) = distribution.sample((i as f64 + 0.5) / n as f64);
+            counts[(x * 4.0) as usize] += 1;
+        }
+        assert_eq!(counts, [1000, 0, 3000, 4000]);
+    }
+
+    #[test]
+    fn sampled_pdf_matches_pdf() {
+        let distribution = Distribution1D::from(vec![0.5, 2.0, 0.0, 1.5, 4.0]);
+        assert!((distribution.integral() - 1.6).abs() < 1e-12);
+        for i in 0..100 {
+            let (x, pdf) = distribution.sample((i as f64 + 0.5) / 100.0);
+            assert!((0.0..1.0).contains(&x));
+            assert!(pdf > 0.0);
+            assert_eq!(pdf, distribution.pdf(x));
+        }
+    }
+
+    #[test]
+    fn sampling_inverts_the_cdf() {
+        let distribution = Distribution1D::from(vec![1.0, 3.0]);
+        // A quarter of the mass lies in the first half, spread evenly across it.
+        assert!((distribution.sample(0.125).0 - 0.25).abs() < 1e-12);
+        assert!((distribution.sample(0.25).0 - 0.5).abs() < 1e-12);
+        assert!((distribution.sample(0.625).0 - 0.75).abs() < 1e-12);
+    }
+
+    #[test]
+    fn zero_function_is_uniform() {
+        let distribution = Distribution1D::from(vec![0.0; 3]);
+        let (x, pdf) = distribution.sample(0.4);
+        assert!((x - 0.4).abs() < 1e-12);
+        assert_eq!(pdf, 1.0);
+    }
+
+    #[test]
+    fn pdf_2d_integrates_to_one() {
+        let (width, height) = (3, 2);
+        let distribution = Distribution2D::from(width, height, &[1.0, 2.0, 0.0, 0.5, 0.0, 4.0]);
+        let mut total = 0.0;
+        for row in 0..height {
+            for column in 0..width {
+                let x = (column as f64 + 0.5) / width as f64;
+                let y = (row as f64 + 0.5) / height as f64;
+                total += distribution.pdf(x, y) / (width * height) as f64;
+            }
+        }
+        assert!((total - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sampled_pdf_2d_matches_pdf_and_the_function() {
+        let function = [1.0, 2.0, 0.0, 0.5, 0.0, 4.0];
+        let distribution = Distribution2D::from(3, 2, &function);
+        for i in 0..20 {
+            for j in 0..20 {
+                let ((x, y), pdf) =
+                    distribution.sample((i as f64 + 0.5) / 20.0, (j as f64 + 0.5) / 20.0);
+                assert!((pdf - distribution.pdf(x, y)).abs() < 1e-12);
+                let cell = (y * 2.0) as usize * 3 + (x * 3.0) as usize;
+                assert!((pdf - function[cell] / distribution.integral()).abs() < 1e-12);
+            }
+        }
+    }
+}
@@ -1,2 +1,6 @@
+pub mod distribution;
 pub mod interval;
+pub mod onb;
+pub mod rng;
 pub mod rt_math;
+pub mod sampler;
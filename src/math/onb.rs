@@ -0,0 +1,76 @@
+use crate::vector::{dot, Vec3};
+
+/// An orthonormal basis used to move directions in and out of a surface's local frame.
+///
+/// In the local frame the surface normal is the `z` axis, which keeps the formulas for
+/// scattering functions short.
+#[derive(Clone, Copy)]
+pub struct Onb {
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+}
+
+impl Onb {
+    /// Builds a basis whose `w` axis is the given unit `normal`.
+    ///
+    /// Uses the branchless construction from Duff et al., "Building an Orthonormal Basis,
+    /// Revisited" (2017), which is continuous everywhere except across the `z = 0` plane.
+    pub fn from_w(normal: &Vec3) -> Self {
+        let sign = 1.0_f64.copysign(normal.z());
+        let a = -1.0 / (sign + normal.z());
+        let b = normal.x() * normal.y() * a;
+        Onb {
+            u: Vec3::from(
+                1.0 + sign * normal.x() * normal.x() * a,
+                sign * b,
+                -sign * normal.x(),
+            ),
+            v: Vec3::from(b, sign + normal.y() * normal.y() * a, -normal.y()),
+            w: *normal,
+        }
+    }
+
+    /// Builds a basis whose `w` axis is the unit `normal` and whose `u` axis follows `tangent`,
+    /// made perpendicular to the normal.
+    ///
+    /// Anisotropic surfaces need this so their `u` direction stays tied to the surface; when the
+    /// tangent is missing or parallel to the normal it falls back to `from_w`.
+    pub fn from_w_tangent(normal: &Vec3, tangent: &Vec3) -> Self {
+        let projected = *tangent - dot(tangent, normal) * *normal;
+        if projected.length() <= 1e-9 * tangent.length().max(1.0) {
+            return Onb::from_w(normal);
+        }
+        let u = projected.normalize();
+        Onb {
+            u,
+            v: Vec3::cross(normal, &u),
+            w: *normal,
+        }
+    }
+
+    /// Returns the first tangent axis.
+    pub fn u(&self) -> Vec3 {
+        self.u
+    }
+
+    /// Returns the second tangent axis.
+    pub fn v(&self) -> Vec3 {
+        self.v
+    }
+
+    /// Returns the normal axis.
+    pub fn w(&self) -> Vec3 {
+        self.w
+    }
+
+    /// Converts a direction expressed in this basis to world space.
+    pub fn world_from_local(&self, a: &Vec3) -> Vec3 {
+        a.x() * self.u + a.y() * self.v + a.z() * self.w
+    }
+
+    /// Converts a world-space direction into this basis.
+    pub fn local_from_world(&self, a: &Vec3) -> Vec3 {
+        Vec3::from(dot(a, &self.u), dot(a, &self.v), dot(a, &self.w))
+    }
+}
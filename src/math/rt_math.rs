@@ -1,6 +1,6 @@
+use super::{rng, sampler};
 use crate::ray;
 use crate::vector;
-use rand::prelude::*;
 // Constants
 pub const INFINITY: f64 = f64::INFINITY;
 pub const PI: f64 = std::f64::consts::PI;
@@ -9,12 +9,18 @@ pub const PI: f64 = std::f64::consts::PI;
 pub fn degrees_to_radians(degrees: f64) -> f64 {
     degrees * PI / 180.0
 }
-// Returns random f64 between 0 and 1
+// Returns random f64 between 0 and 1, from the thread's sampler if one is installed and
+// otherwise from the thread's seeded stream
 pub fn random_number() -> f64 {
-    rand::thread_rng().gen::<f64>()
+    sampler::next_1d().unwrap_or_else(rng::next_f64)
+}
+
+// Returns a pair of random f64s between 0 and 1, which a sampler spreads evenly as a pair
+pub fn random_pair() -> (f64, f64) {
+    sampler::next_2d().unwrap_or_else(|| (rng::next_f64(), rng::next_f64()))
 }
 
 // Returns random f64 within a specified range
 pub fn random_number_in_range(min: f64, max: f64) -> f64 {
-    rand::thread_rng().gen_range(min..=max)
+    min + (max - min) * random_number()
 }
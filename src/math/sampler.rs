@@ -0,0 +1,516 @@
+use super::rng::{hash, mix_bits, Pcg32};
+use std::cell::RefCell;
+use std::sync::OnceLock;
+
+/// Largest `f64` below one, so sample values stay in `[0, 1)`.
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+/// Number of dimensions the Halton sampler covers before falling back to independent samples.
+const HALTON_DIMENSIONS: usize = 256;
+
+thread_local! {
+    /// The sampler supplying this thread's random numbers, if any.
+    static SAMPLER: RefCell<Option<Box<dyn Sampler>>> = RefCell::new(None);
+}
+
+/// Produces the random numbers for one pixel sample at a time.
+///
+/// Each call to `get_1d` or `get_2d` moves on to the next dimension of the sample, so the
+/// camera, lens and materials draw their numbers in the same order for every sample of a pixel.
+/// Samplers that spread those numbers evenly across the samples of each dimension, instead of
+/// drawing them independently, leave less noise at the same sample count.
+pub trait Sampler {
+    /// Starts sample number `sample_index` of `pixel`, going back to the first dimension.
+    fn start_pixel_sample(&mut self, pixel: (usize, usize), sample_index: usize);
+    /// Returns the next dimension of the sample, in `[0, 1)`.
+    fn get_1d(&mut self) -> f64;
+    /// Returns the next two dimensions of the sample, which are spread evenly as a pair.
+    fn get_2d(&mut self) -> (f64, f64);
+    /// Moves ahead to `dimension`, unless the sample has already gone past it.
+    fn skip_to_dimension(&mut self, dimension: usize);
+}
+
+/// Which `Sampler` the camera uses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SamplePattern {
+    /// Independent uniform random numbers.
+    Independent,
+    /// Jittered samples: one in each of the strata each dimension is split into.
+    Stratified,
+    /// The Halton sequence, randomly shifted for each pixel.
+    Halton,
+    /// Owen-scrambled Sobol points, shuffled and scrambled independently per pixel and per
+    /// dimension with Burley's hash-based method.
+    Sobol,
+}
+
+impl SamplePattern {
+    /// Creates a sampler for pixels with `samples_per_pixel` samples.
+    ///
+    /// `seed` keys every random stream and scramble, so renders that should not share their
+    /// noise, such as the frames of an animation, need different seeds.
+    pub fn sampler(&self, samples_per_pixel: usize, seed: u64) -> Box<dyn Sampler> {
+        match self {
+            SamplePattern::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplePattern::Stratified => Box::new(StratifiedSampler::new(samples_per_pixel, seed)),
+            SamplePattern::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplePattern::Sobol => Box::new(SobolSampler::new(seed)),
+        }
+    }
+}
+
+/// Makes `sampler` supply the random numbers drawn on this thread, returning the sampler it
+/// replaces. With no sampler installed, random numbers are drawn independently.
+pub fn install(sampler: Option<Box<dyn Sampler>>) -> Option<Box<dyn Sampler>> {
+    SAMPLER.with(|active| active.replace(sampler))
+}
+
+/// Starts a pixel sample on this thread's sampler, if one is installed.
+pub fn start_pixel_sample(pixel: (usize, usize), sample_index: usize) {
+    SAMPLER.with(|active| {
+        if let Some(sampler) = active.borrow_mut().as_mut() {
+            sampler.start_pixel_sample(pixel, sample_index);
+        }
+    });
+}
+
+/// Moves this thread's sampler ahead to `dimension`, if one is installed.
+///
+/// Calling this at fixed points, such as the start of every bounce, keeps the dimensions lined
+/// up between samples that drew different amounts of random numbers before.
+pub fn skip_to_dimension(dimension: usize) {
+    SAMPLER.with(|active| {
+        if let Some(sampler) = active.borrow_mut().as_mut() {
+            sampler.skip_to_dimension(dimension);
+        }
+    });
+}
+
+/// Returns the next dimension from this thread's sampler, or `None` if none is installed.
+pub fn next_1d() -> Option<f64> {
+    SAMPLER.with(|active| active.borrow_mut().as_mut().map(|sampler| sampler.get_1d()))
+}
+
+/// Returns the next two dimensions from this thread's sampler, or `None` if none is installed.
+pub fn next_2d() -> Option<(f64, f64)> {
+    SAMPLER.with(|active| active.borrow_mut().as_mut().map(|sampler| sampler.get_2d()))
+}
+
+/// Draws every number independently.
+pub struct IndependentSampler {
+    seed: u64,
+    rng: Pcg32,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        IndependentSampler {
+            seed,
+            rng: Pcg32::from(seed, 0),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, pixel: (usize, usize), sample_index: usize) {
+        self.rng = sample_stream(self.seed, pixel, sample_index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.next_f64()
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.rng.next_f64(), self.rng.next_f64())
+    }
+
+    fn skip_to_dimension(&mut self, _dimension: usize) {}
+}
+
+/// Splits each dimension into one stratum per sample, and each pair of dimensions into a grid of
+/// about as many cells, then places every sample at a random point in its own stratum.
+///
+/// Which sample gets which stratum is shuffled independently for each dimension, so strata of
+/// different dimensions are not correlated.
+pub struct StratifiedSampler {
+    samples_per_pixel: usize,
+    columns: usize,
+    rows: usize,
+    seed: u64,
+    pixel: (usize, usize),
+    sample_index: usize,
+    dimension: usize,
+    rng: Pcg32,
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        let samples_per_pixel = samples_per_pixel.max(1);
+        let columns = (samples_per_pixel as f64).sqrt().ceil() as usize;
+        StratifiedSampler {
+            samples_per_pixel,
+            columns,
+            rows: samples_per_pixel.div_ceil(columns),
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+            rng: Pcg32::from(seed, 0),
+        }
+    }
+
+    /// Returns the stratum, out of `strata`, this sample occupies in the current dimension.
+    fn stratum(&mut self, strata: usize) -> usize {
+        let seed = hash(&[
+            self.seed,
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            self.dimension as u64,
+        ]);
+        self.dimension += 1;
+        permutation_element(self.sample_index as u32, strata as u32, seed as u32) as usize
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, pixel: (usize, usize), sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index % self.samples_per_pixel;
+        self.dimension = 0;
+        self.rng = sample_stream(self.seed, pixel, sample_index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let stratum = self.stratum(self.samples_per_pixel);
+        ((stratum as f64 + self.rng.next_f64()) / self.samples_per_pixel as f64)
+            .min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        // With fewer samples than cells, the shuffle leaves a random set of cells empty.
+        let cell = self.stratum(self.columns * self.rows);
+        let x = (cell % self.columns) as f64 + self.rng.next_f64();
+        let y = (cell / self.columns) as f64 + self.rng.next_f64();
+        (
+            (x / self.columns as f64).min(ONE_MINUS_EPSILON),
+            (y / self.rows as f64).min(ONE_MINUS_EPSILON),
+        )
+    }
+
+    fn skip_to_dimension(&mut self, dimension: usize) {
+        self.dimension = self.dimension.max(dimension);
+    }
+}
+
+/// Uses the radical inverse of the sample index in a different prime base for each dimension.
+///
+/// Every pixel would see the same points, so the digits are Owen-scrambled with permutations
+/// chosen by hashing the pixel and dimension. This also breaks up the clumping of the higher
+/// dimensions, whose large bases only fill part of the unit interval at low sample counts.
+pub struct HaltonSampler {
+    seed: u64,
+    pixel: (usize, usize),
+    sample_index: usize,
+    dimension: usize,
+    rng: Pcg32,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        HaltonSampler {
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+            rng: Pcg32::from(seed, 0),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, pixel: (usize, usize), sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+        self.rng = sample_stream(self.seed, pixel, sample_index);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        if dimension >= HALTON_DIMENSIONS {
+            return self.rng.next_f64();
+        }
+        let seed = hash(&[
+            self.seed,
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            dimension as u64,
+        ]);
+        scrambled_radical_inverse(self.sample_index as u64, primes()[dimension], seed)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        (self.get_1d(), self.get_1d())
+    }
+
+    fn skip_to_dimension(&mut self, dimension: usize) {
+        self.dimension = self.dimension.max(dimension);
+    }
+}
+
+/// Uses the first two dimensions of the Sobol sequence for every pair of dimensions, with the
+/// sample order shuffled and the points Owen-scrambled by hashing the pixel and dimension.
+///
+/// Only the first two Sobol dimensions are well distributed at low sample counts, so rather than
+/// reaching for higher ones each dimension gets its own independently scrambled copy ("padding").
+/// Scrambling keeps the points stratified in every power-of-two partition, and works best when
+/// the sample count is a power of two.
+pub struct SobolSampler {
+    seed: u64,
+    pixel: (usize, usize),
+    sample_index: usize,
+    dimension: usize,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        SobolSampler {
+            seed,
+            pixel: (0, 0),
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Returns the shuffled sample index and scrambling seed for the current dimension.
+    fn next_dimension(&mut self) -> (u32, u64) {
+        let seed = hash(&[
+            self.seed,
+            self.pixel.0 as u64,
+            self.pixel.1 as u64,
+            self.dimension as u64,
+        ]);
+        self.dimension += 1;
+        let index = nested_uniform_scramble(self.sample_index as u32, seed as u32);
+        (index, seed)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, pixel: (usize, usize), sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (index, seed) = self.next_dimension();
+        let x = nested_uniform_scramble(index.reverse_bits(), (seed >> 32) as u32);
+        fixed_to_float(x)
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        let (index, seed) = self.next_dimension();
+        let x = nested_uniform_scramble(index.reverse_bits(), (seed >> 32) as u32);
+        let y = nested_uniform_scramble(sobol_second_dimension(index), mix_bits(seed) as u32);
+        (fixed_to_float(x), fixed_to_float(y))
+    }
+
+    fn skip_to_dimension(&mut self, dimension: usize) {
+        self.dimension = self.dimension.max(dimension);
+    }
+}
+
+/// Returns the random stream of one pixel sample, so that its numbers do not depend on which
+/// thread renders it or in what order.
+fn sample_stream(seed: u64, pixel: (usize, usize), sample_index: usize) -> Pcg32 {
+    Pcg32::from(
+        seed,
+        hash(&[pixel.0 as u64, pixel.1 as u64, sample_index as u64]),
+    )
+}
+
+/// Returns the second dimension of the Sobol sequence, whose generator is the Pascal matrix.
+fn sobol_second_dimension(index: u32) -> u32 {
+    let mut direction = 1u32 << 31;
+    let mut value = 0;
+    let mut index = index;
+    while index != 0 {
+        if index & 1 != 0 {
+            value ^= direction;
+        }
+        direction ^= direction >> 1;
+        index >>= 1;
+    }
+    value
+}
+
+/// Owen-scrambles a 32-bit fixed-point value: each bit is flipped depending on the bits above
+/// it, following Burley's "Practical Hash-based Owen Scrambling".
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+/// A hash in which each bit only depends on the bits below it.
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x
+}
+
+fn fixed_to_float(x: u32) -> f64 {
+    x as f64 / (1u64 << 32) as f64
+}
+
+/// Mirrors the base-`base` digits of `index` about the radix point, permuting each digit by a
+/// permutation chosen from `seed` and the digits before it.
+///
+/// The leading zeros are permuted as well, down to the precision of an `f64`.
+fn scrambled_radical_inverse(mut index: u64, base: u64, seed: u64) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let mut scale = 1.0;
+    let mut digits = 0u64;
+    while 1.0 - scale < 1.0 {
+        let digit = index % base;
+        index /= base;
+        let permutation = mix_bits(seed ^ digits) as u32;
+        digits = digits * base + permutation_element(digit as u32, base as u32, permutation) as u64;
+        scale *= inverse_base;
+    }
+    (digits as f64 * scale).min(ONE_MINUS_EPSILON)
+}
+
+/// Returns the first `HALTON_DIMENSIONS` primes.
+fn primes() -> &'static [u64] {
+    static PRIMES: OnceLock<Vec<u64>> = OnceLock::new();
+    PRIMES.get_or_init(|| {
+        let mut primes: Vec<u64> = Vec::with_capacity(HALTON_DIMENSIONS);
+        let mut candidate = 2;
+        while primes.len() < HALTON_DIMENSIONS {
+            if primes.iter().all(|prime| candidate % prime != 0) {
+                primes.push(candidate);
+            }
+            candidate += 1;
+        }
+        primes
+    })
+}
+
+/// Returns element `index` of a random permutation of `0..length` chosen by `seed`, using
+/// Kensler's "Correlated Multi-Jittered Sampling" hash.
+fn permutation_element(mut index: u32, length: u32, seed: u32) -> u32 {
+    let mut mask = length.wrapping_sub(1);
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+    loop {
+        index ^= seed;
+        index = index.wrapping_mul(0xe170893d);
+        index ^= seed >> 16;
+        index ^= (index & mask) >> 4;
+        index ^= seed >> 8;
+        index = index.wrapping_mul(0x0929eb3f);
+        index ^= seed >> 23;
+        index ^= (index & mask) >> 1;
+        index = index.wrapping_mul(1 | seed >> 27);
+        index = index.wrapping_mul(0x6935fa69);
+        index ^= (index & mask) >> 11;
+        index = index.wrapping_mul(0x74dcb303);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0x9e501cc3);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0xc860a3df);
+        index &= mask;
+        index ^= index >> 5;
+        if index < length {
+            return index.wrapping_add(seed) % length;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws `count` samples of `pixel`, each skipping to `dimension` and taking a 1D and a 2D
+    /// value from there.
+    fn draw(
+        sampler: &mut dyn Sampler,
+        count: usize,
+        pixel: (usize, usize),
+        dimension: usize,
+    ) -> Vec<(f64, (f64, f64))> {
+        (0..count)
+            .map(|index| {
+                sampler.start_pixel_sample(pixel, index);
+                sampler.skip_to_dimension(dimension);
+                (sampler.get_1d(), sampler.get_2d())
+            })
+            .collect()
+    }
+
+    /// Checks that `values` put exactly one value in each of `strata` equal intervals.
+    fn one_per_stratum(values: impl Iterator<Item = f64>, strata: usize) -> bool {
+        let mut counts = vec![0; strata];
+        values.for_each(|value| counts[(value * strata as f64) as usize] += 1);
+        counts.iter().all(|&count| count == 1)
+    }
+
+    /// Checks that `points` put exactly one point in each cell of a `columns` by `rows` grid.
+    fn one_per_cell(points: &[(f64, f64)], columns: usize, rows: usize) -> bool {
+        let mut counts = vec![0; columns * rows];
+        for &(x, y) in points {
+            counts[(y * rows as f64) as usize * columns + (x * columns as f64) as usize] += 1;
+        }
+        counts.iter().all(|&count| count == 1)
+    }
+
+    #[test]
+    fn stratified_samples_fill_every_stratum() {
+        let mut sampler = StratifiedSampler::new(16, 1);
+        for pixel in [(0, 0), (5, 9)] {
+            let samples = draw(&mut sampler, 16, pixel, 3);
+            assert!(one_per_stratum(samples.iter().map(|sample| sample.0), 16));
+            let points: Vec<(f64, f64)> = samples.iter().map(|sample| sample.1).collect();
+            assert!(one_per_cell(&points, 4, 4));
+        }
+    }
+
+    #[test]
+    fn halton_dimensions_are_stratified_in_their_base() {
+        let mut sampler = HaltonSampler::new(1);
+        // Dimensions 0 to 2 use bases 2, 3 and 5.
+        let samples = draw(&mut sampler, 225, (3, 7), 0);
+        assert!(one_per_stratum(samples.iter().take(128).map(|s| s.0), 128));
+        assert!(one_per_stratum(samples.iter().take(81).map(|s| s.1 .0), 81));
+        assert!(one_per_stratum(
+            samples.iter().take(125).map(|s| s.1 .1),
+            125
+        ));
+
+        // Bases 3 and 5 together: 3^2 * 5^2 points, one in each box of a 9 by 25 grid.
+        let points: Vec<(f64, f64)> = samples.iter().map(|sample| sample.1).collect();
+        assert!(one_per_cell(&points, 9, 25));
+    }
+
+    #[test]
+    fn sobol_points_fill_every_elementary_interval() {
+        let mut sampler = SobolSampler::new(1);
+        for pixel in [(0, 0), (12, 4)] {
+            for dimension in [0, 6] {
+                let samples = draw(&mut sampler, 64, pixel, dimension);
+                assert!(one_per_stratum(samples.iter().map(|sample| sample.0), 64));
+                let points: Vec<(f64, f64)> = samples.iter().map(|sample| sample.1).collect();
+                for bits in 0..=6 {
+                    assert!(one_per_cell(&points, 1 << bits, 1 << (6 - bits)));
+                }
+            }
+        }
+    }
+}
@@ -2,18 +2,41 @@ use std::env;
 use std::fs::File;
 use std::io::Write;
 
-use rand::random;
-
+use crate::animation::camera_animation::{view_basis, CameraAnimation};
 use crate::color::{self, *};
+use crate::math::distribution::Distribution2D;
 use crate::math::interval::Interval;
-use crate::math::rt_math::{degrees_to_radians, random_number};
+use crate::math::onb::Onb;
+use crate::math::rng;
+use crate::math::rt_math::{degrees_to_radians, random_number, random_pair, PI};
+use crate::math::sampler::{self, SamplePattern};
+use crate::perspective_camera::filter::{FilterSampler, PixelFilter};
+use crate::perspective_camera::lens_system::LensSystem;
+use crate::perspective_camera::projection::{equirectangular_direction, Projection};
+use crate::perspective_camera::region::RenderRegion;
+use crate::perspective_camera::shutter::ShutterCurve;
+use crate::perspective_camera::stereo::StereoMode;
 use crate::ray::Ray;
+use crate::spectrum::{self, Wavelengths};
+use crate::textures::{image::ImageTexture, texture::Texture};
 use crate::traceable::*;
-use crate::vector::{Point3, Vec3};
+use crate::vector::{dot, Point3, Vec3};
 use rayon::prelude::*;
 use std::io::{self};
+use std::ops::Range;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+
+/// Distance a ray must travel before it can hit something, to avoid re-hitting the surface it
+/// left.
+pub const RAY_EPSILON: f64 = 0.1;
+
+/// Sampler dimensions set aside for generating a camera ray: the pixel filter, time, lens and
+/// wavelengths.
+const CAMERA_DIMENSIONS: usize = 8;
+/// Sampler dimensions set aside for each bounce of a path.
+const BOUNCE_DIMENSIONS: usize = 8;
+
 /// A camera in the scene responsible for rendering the view.
 pub struct Camera {
     /// The aspect ratio of the image (width over height).
@@ -24,20 +47,81 @@ pub struct Camera {
     pub image_width: usize,
     /// How many anti-aliasing samples
     pub pixel_samples: usize,
+    /// Reconstruction filter that weighs each sample by its offset from the pixel center
+    pub pixel_filter: PixelFilter,
+    /// How the random numbers for each pixel's samples are spread out
+    pub sample_pattern: SamplePattern,
+    /// How far, in pixels, the filter reaches from the pixel center
+    pub filter_radius: f64,
     /// Maximum number of ray bounces
     pub max_depth: usize,
+    /// Number of bounces before Russian roulette may terminate a path
+    pub russian_roulette_depth: usize,
+    /// How scene points are projected onto the image
+    pub projection: Projection,
     /// Vertical FOV
     pub vfov: f64,
+    /// Height of the view in world units for the orthographic projection
+    pub view_height: f64,
+    /// Field of view across the fisheye image circle, in degrees
+    pub fisheye_fov: f64,
     /// Variation angle of rays going through each pixel
     pub defocus_angle: f64,
+    /// Number of aperture blades, giving polygonal bokeh; fewer than 3 means a round aperture
+    pub aperture_blades: usize,
+    /// Rotation of the aperture shape in degrees
+    pub aperture_rotation: f64,
+    /// Grayscale image of the aperture's shape, overriding the blades when set
+    pub aperture_image: Option<Arc<ImageTexture>>,
+    /// Strength of mechanical vignetting, which clips the aperture into a cat's eye toward the
+    /// corners of the frame; 0 disables it and 1 leaves about 40% of the aperture in the corners
+    pub cat_eye: f64,
+    /// Time at which the shutter opens
+    pub shutter_open: f64,
+    /// Time at which the shutter closes
+    pub shutter_close: f64,
+    /// How the shutter's opening varies between `shutter_open` and `shutter_close`
+    pub shutter_curve: ShutterCurve,
+    /// Time a rolling shutter takes to read out from the top row to the bottom; 0 exposes all
+    /// rows together
+    pub rolling_shutter_duration: f64,
+    /// Lens prescription to trace camera rays through instead of an ideal thin lens; replaces
+    /// `vfov` and the aperture settings for the perspective projection
+    pub lens_system: Option<LensSystem>,
+    /// Diagonal of the film behind `lens_system`, in millimeters
+    pub film_diagonal: f64,
     /// Distance from camera origin point to plane of ideal focus
     pub focus_distance: f64,
+    /// Horizontal and vertical shift of the view, as fractions of its width and height; moves
+    /// the framing without turning the camera, keeping vertical lines parallel
+    pub lens_shift: (f64, f64),
+    /// Tilt and swing of the plane of focus in degrees; positive values move its top and its
+    /// right side further away, as when focusing along a floor or a wall
+    pub focal_tilt: (f64, f64),
     /// Where camera is looking from
     pub camera_origin: Point3,
     /// What camera is looking at
     pub camera_target: Point3,
     /// Vector pointing up
     pub up_vector: Vec3,
+    /// Whether to render a stereo pair, and how to arrange it
+    pub stereo: StereoMode,
+    /// Distance between the eyes for stereo rendering
+    pub interocular_distance: f64,
+    /// Distance at which the two eyes' views line up on the image
+    pub convergence_distance: f64,
+    /// Part of the image to render; everything when unset
+    pub render_region: Option<RenderRegion>,
+    /// Write only the `render_region` instead of a full-size image with the rest left black
+    pub crop_output: bool,
+    /// Keyframed camera parameters, applied at `shutter_open`; camera movement during the
+    /// exposure blurs the image
+    pub animation: Option<CameraAnimation>,
+    /// Where `render` writes the image; for `render_sequence`, a run of `#` is replaced with
+    /// the frame number
+    pub output_path: String,
+    /// Trace randomly sampled wavelengths instead of RGB, enabling dispersion
+    pub spectral: bool,
     /// The height of the image in pixels.
     image_height: usize,
     /// The camera's position in space.
@@ -52,6 +136,14 @@ pub struct Camera {
     defocus_disc_u: Vec3,
     /// Defocus disc vertical radius
     defocus_disc_v: Vec3,
+    /// Defocus disc radius
+    defocus_radius: f64,
+    /// Normal of the plane of focus, facing the camera
+    focal_plane_normal: Vec3,
+    /// Distribution for sampling points on `aperture_image`
+    aperture_distribution: Option<Distribution2D>,
+    /// Draws sample offsets following `pixel_filter`
+    filter_sampler: Option<FilterSampler>,
     /// Frame basis vectors
     u: Vec3,
     v: Vec3,
@@ -69,6 +161,18 @@ impl Camera {
             center: Point3::new(),
             defocus_angle: 0.0,
             focus_distance: 0.0,
+            lens_shift: (0.0, 0.0),
+            focal_tilt: (0.0, 0.0),
+            aperture_blades: 0,
+            aperture_rotation: 0.0,
+            aperture_image: None,
+            cat_eye: 0.0,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            shutter_curve: ShutterCurve::Box,
+            rolling_shutter_duration: 0.0,
+            lens_system: None,
+            film_diagonal: 35.0,
             camera_origin: Point3::from(0.0, 0.0, -1.0),
             camera_target: Point3::from(0.0, 0.0, 0.0),
             up_vector: Vec3::from(0.0, 1.0, 0.0),
@@ -77,19 +181,52 @@ impl Camera {
             pixel_delta_v: Vec3::new(),
             defocus_disc_u: Vec3::new(),
             defocus_disc_v: Vec3::new(),
+            defocus_radius: 0.0,
+            focal_plane_normal: Vec3::new(),
+            aperture_distribution: None,
+            filter_sampler: None,
             pixel_samples: 10,
+            pixel_filter: PixelFilter::Box,
+            sample_pattern: SamplePattern::Independent,
+            filter_radius: 0.5,
             max_depth: 10,
+            russian_roulette_depth: 3,
+            projection: Projection::Perspective,
             vfov: 90.0,
+            view_height: 2.0,
+            fisheye_fov: 180.0,
+            stereo: StereoMode::Off,
+            interocular_distance: 0.064,
+            convergence_distance: 10.0,
+            render_region: None,
+            crop_output: false,
+            animation: None,
+            output_path: String::from("images/image.ppm"),
+            spectral: false,
             u: Vec3::new(),
             v: Vec3::new(),
             w: Vec3::new(),
         }
     }
 
+    /// Sets the shutter to stay open for the fraction `shutter_angle / 360` of a frame at
+    /// `frame_rate` frames per second, starting at `shutter_open`.
+    ///
+    /// Film cameras commonly use 180 degrees; object motion must then be given in seconds.
+    pub fn set_shutter_angle(&mut self, frame_rate: f64, shutter_angle: f64) {
+        self.shutter_close = self.shutter_open + shutter_angle / 360.0 / frame_rate;
+    }
+
     /// Determines the color seen in the direction of the given ray.
     ///
-    /// If the ray intersects with an object in the world, it computes the color based on the material and lighting.
-    /// Otherwise, it returns a gradient color representing the sky.
+    /// The path is followed iteratively: at every intersection the light arriving directly from
+    /// the scene's lights is added, then the material scatters the ray and the path's throughput
+    /// is multiplied by the attenuation. Rays that escape the scene pick up a gradient color
+    /// representing the sky.
+    ///
+    /// After `russian_roulette_depth` bounces, dim paths are randomly terminated and the
+    /// survivors are brightened to compensate, which keeps the estimate unbiased. `max_depth`
+    /// remains a hard cap on the number of bounces.
     ///
     /// # Arguments
     ///
@@ -108,73 +245,385 @@ impl Camera {
     /// let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0));
     /// let color = camera.get_ray_color(&ray, &world);
     /// ```
-    fn get_ray_color(ray: &Ray, depth: usize, world: &Traceables) -> Color {
-        let mut record: HitRecord = HitRecord::new();
-        if depth <= 0 {
-            return Color::new();
-        }
-        // If a ray intersects with the sphere in the scene, return a color.
-        if world.hit(ray, Interval::new(0.1, f64::INFINITY), &mut record) {
+    fn get_ray_color(&self, ray: &Ray, world: &Traceables) -> Color {
+        let mut color = Color::new();
+        let mut throughput = Color::from(1.0, 1.0, 1.0);
+        let mut ray = *ray;
+
+        for bounce in 0..self.max_depth {
+            // Start every bounce on its own dimensions, whatever earlier bounces used
+            sampler::skip_to_dimension(CAMERA_DIMENSIONS + bounce * BOUNCE_DIMENSIONS);
+            let mut record: HitRecord = HitRecord::new();
+            if !world.hit(&ray, Interval::new(RAY_EPSILON, f64::INFINITY), &mut record) {
+                // Normalize the ray's direction vector.
+                let unit_direction: Vec3 = ray.direction().normalize();
+                // Calculate blending factor for color interpolation.
+                let blend_factor = 0.5 * (unit_direction.y() + 1.0);
+                // Linearly interpolate between white and light blue colors based on the blend factor.
+                let sky = (1.0 - blend_factor) * Color::from(1.0, 1.0, 1.0)
+                    + blend_factor * Color::from(0.5, 0.7, 1.0);
+                return color + throughput * spectrum::uplift(&ray, sky);
+            }
+
+            color += throughput * Self::sample_lights(&ray, &record, world);
+
             let mut scattered_ray: Ray = Ray::new();
             let mut attenuation: Color = Color::new();
-            if record
+            if !record
                 .material()
-                .scatter(ray, &record, &mut attenuation, &mut scattered_ray)
+                .scatter(&ray, &record, &mut attenuation, &mut scattered_ray)
             {
-                return attenuation * Self::get_ray_color(&scattered_ray, depth - 1, world);
+                return color;
             }
-            return Color::new();
-        } else {
-            // Normalize the ray's direction vector.
-            let unit_direction: Vec3 = ray.direction().normalize();
-            // Calculate blending factor for color interpolation.
-            let blend_factor = 0.5 * (unit_direction.y() + 1.0);
-            // Linearly interpolate between white and light blue colors based on the blend factor.
-            return (1.0 - blend_factor) * Color::from(1.0, 1.0, 1.0)
-                + blend_factor * Color::from(0.5, 0.7, 1.0);
+            throughput = throughput * attenuation;
+            if scattered_ray.wavelengths().is_none() {
+                scattered_ray.set_wavelengths(ray.wavelengths());
+            }
+            ray = scattered_ray;
+
+            // Russian roulette: terminate dim paths with probability inversely related to their
+            // throughput and reweight the ones that survive.
+            let max_throughput = throughput.max_component();
+            if max_throughput <= 0.0 {
+                return color;
+            }
+            if bounce + 1 >= self.russian_roulette_depth && max_throughput < 1.0 {
+                let termination_probability = 1.0 - max_throughput;
+                if random_number() < termination_probability {
+                    return color;
+                }
+                throughput /= 1.0 - termination_probability;
+            }
+        }
+        color
+    }
+
+    /// Gathers light arriving directly from the scene's punctual lights at a hit point.
+    ///
+    /// Each light is checked for visibility with a shadow ray through `Traceables::hit`;
+    /// unoccluded light is weighted by the material's response toward that light.
+    fn sample_lights(ray: &Ray, record: &HitRecord, world: &Traceables) -> Color {
+        let material = record.material();
+        let mut direct_light = Color::new();
+        for light in world.lights() {
+            let mut direction = Vec3::new();
+            let mut distance = 0.0;
+            let mut radiance = Color::new();
+            if !light.illuminate(
+                &record.point(),
+                &mut direction,
+                &mut distance,
+                &mut radiance,
+            ) {
+                continue;
+            }
+            let response = material.eval(ray, record, &direction);
+            if response.approx_zero() {
+                continue;
+            }
+            if world.occluded(record.point(), direction, distance, ray.time()) {
+                continue;
+            }
+            direct_light += response * spectrum::uplift(ray, radiance);
+        }
+        direct_light
+    }
+
+    /// Returns a randomly sampled ray through the point `(px, py)` pixels from the center of
+    /// pixel `(i, j)`, with the weight its radiance is scaled by, or `None` if the point lies
+    /// outside the area the projection covers, such as the corners around a fisheye image circle.
+    ///
+    /// `eye_offset` moves the viewpoint sideways for stereo rendering, negative for the left eye.
+    fn get_ray(
+        &self,
+        i: usize,
+        j: usize,
+        (px, py): (f64, f64),
+        eye_offset: f64,
+    ) -> Option<(Ray, f64)> {
+        let ray_time: f64 = self.sample_time(j);
+
+        // The panoramic projections give a direction in the camera's basis.
+        let direction = match self.projection {
+            Projection::Perspective if self.lens_system.is_some() => {
+                let film_position = (i as f64 + 0.5 + px, j as f64 + 0.5 + py);
+                return self.get_lens_system_ray(film_position, eye_offset, ray_time);
+            }
+            Projection::Perspective | Projection::Orthographic => {
+                let pixel_sample = self.pixel_origin
+                    + ((i as f64 + px) * self.pixel_delta_u)
+                    + ((j as f64 + py) * self.pixel_delta_v);
+                let film_position = self.film_position(i as f64 + 0.5 + px, j as f64 + 0.5 + py);
+                let ray = self.get_planar_ray(pixel_sample, film_position, eye_offset, ray_time)?;
+                return Some((ray, 1.0));
+            }
+            Projection::Equirectangular => equirectangular_direction(
+                i as f64 + 0.5 + px,
+                j as f64 + 0.5 + py,
+                self.image_width,
+                self.image_height,
+            ),
+            Projection::Fisheye(mapping) => mapping.direction(
+                i as f64 + 0.5 + px,
+                j as f64 + 0.5 + py,
+                self.image_width,
+                self.image_height,
+                self.fisheye_fov,
+            )?,
+        };
+        let ray_direction =
+            direction.x() * self.u + direction.y() * self.v + direction.z() * self.w;
+        Some((
+            self.get_directional_ray(ray_direction, eye_offset, ray_time),
+            1.0,
+        ))
+    }
+
+    /// Moves a camera ray along with the camera to where it is at the ray's time.
+    ///
+    /// Rays are generated for the camera as it is at `shutter_open`; keeping their position
+    /// relative to the camera while it moves gives camera motion blur.
+    fn follow_animation(&self, ray: Ray) -> Ray {
+        let Some(animation) = self.animation.as_ref().filter(|a| a.is_moving()) else {
+            return ray;
+        };
+        let (origin, target) =
+            animation.view_at(ray.time(), self.camera_origin, self.camera_target);
+        let (u, v, w) = view_basis(origin, target, self.up_vector);
+        let relative = |vector: Vec3| {
+            dot(&vector, &self.u) * u + dot(&vector, &self.v) * v + dot(&vector, &self.w) * w
+        };
+        Ray::from(
+            origin + relative(ray.origin() - self.center),
+            relative(ray.direction()),
+            ray.time(),
+        )
+    }
+
+    /// Returns a ray through `pixel_sample` on the viewport for the planar projections.
+    ///
+    /// Perspective rays leave the lens center and pass through the viewport, which lies in the
+    /// plane of focus. Orthographic rays leave the viewport itself, at the camera, and travel
+    /// straight ahead; the focus distance only matters to them for depth of field.
+    ///
+    /// For stereo the eye moves by `eye_offset`. Perspective viewports shift with it, off axis,
+    /// and orthographic rays lean toward the center, so both eyes keep the same view direction
+    /// and agree on points at the convergence distance.
+    ///
+    /// Returns `None` when vignetting at `film_position` blocks the sampled lens point.
+    fn get_planar_ray(
+        &self,
+        pixel_sample: Point3,
+        film_position: (f64, f64),
+        eye_offset: f64,
+        ray_time: f64,
+    ) -> Option<Ray> {
+        let eye_shift = eye_offset * self.u;
+        let (lens_center, direction) = match self.projection {
+            Projection::Orthographic => (
+                pixel_sample + eye_shift,
+                -self.w - eye_shift / self.convergence_distance,
+            ),
+            _ => {
+                let lens_center = self.center + eye_shift;
+                let pixel_sample = pixel_sample
+                    + (1.0 - self.focus_distance / self.convergence_distance) * eye_shift;
+                (lens_center, pixel_sample - lens_center)
+            }
+        };
+        if self.defocus_angle <= 0.0 {
+            return Some(Ray::from(lens_center, direction, ray_time));
+        }
+
+        // Rays from across the lens meet where this ray crosses the plane of focus.
+        let ray_origin = lens_center + self.sample_defocus_disc(film_position)?;
+        let untilted = lens_center + self.focus_distance / dot(&direction, &-self.w) * direction;
+        let ray_direction = self.focus_point(lens_center, untilted) - ray_origin;
+        Some(Ray::from(ray_origin, ray_direction, ray_time))
+    }
+
+    /// Returns the point in focus along the line from `lens_center` through `untilted`, the
+    /// point where the line crosses the untilted plane of focus.
+    ///
+    /// Without tilt this is `untilted` itself. A tilted plane of focus is instead intersected
+    /// with the line, so each pixel focuses at its own distance, following the Scheimpflug
+    /// principle.
+    fn focus_point(&self, lens_center: Point3, untilted: Point3) -> Point3 {
+        if self.focal_tilt == (0.0, 0.0) {
+            return untilted;
+        }
+        let direction = untilted - lens_center;
+        let plane_point = self.center - self.focus_distance * self.w;
+        let denominator = dot(&direction, &self.focal_plane_normal);
+        let t = dot(&(plane_point - lens_center), &self.focal_plane_normal) / denominator;
+        match t > 0.0 && t.is_finite() {
+            true => lens_center + t * direction,
+            // The plane runs parallel to or behind this line, so nothing along it is sharp;
+            // fall back to the untilted plane.
+            _ => untilted,
+        }
+    }
+
+    /// Returns a ray through `lens_system` from the film position `(x, y)`, in pixels, with
+    /// its weight from `LensSystem::generate_ray`.
+    ///
+    /// The film sits at the camera origin, facing `camera_target`. The lens forms an upside-down
+    /// image, so film positions are mirrored through its center to keep the picture upright.
+    fn get_lens_system_ray(
+        &self,
+        (x, y): (f64, f64),
+        eye_offset: f64,
+        ray_time: f64,
+    ) -> Option<(Ray, f64)> {
+        let lens_system = self.lens_system.as_ref()?;
+        let diagonal_pixels = f64::sqrt(
+            (self.image_width * self.image_width + self.image_height * self.image_height) as f64,
+        );
+        let meters_per_pixel = 0.001 * self.film_diagonal / diagonal_pixels;
+        let film_x = (x - 0.5 * self.image_width as f64) * meters_per_pixel;
+        let film_y = (0.5 * self.image_height as f64 - y) * meters_per_pixel;
+
+        let (lens_ray, weight) = lens_system.generate_ray((-film_x, -film_y))?;
+        let to_world =
+            |vector: Vec3| vector.x() * self.u + vector.y() * self.v - vector.z() * self.w;
+        let ray = Ray::from(
+            self.center + eye_offset * self.u + to_world(lens_ray.origin()),
+            to_world(lens_ray.direction()),
+            ray_time,
+        );
+        Some((ray, weight))
+    }
+
+    /// Returns a ray from the camera center along `direction` for the panoramic projections.
+    ///
+    /// For stereo the eye moves by `eye_offset` sideways to `direction`, around a circle as in
+    /// omni-directional stereo, and looks toward the point at the convergence distance.
+    ///
+    /// Depth of field works as for a lens pointed along `direction`: the origin is jittered
+    /// across a disc facing that way, and the ray aimed at the point in focus.
+    fn get_directional_ray(&self, direction: Vec3, eye_offset: f64, ray_time: f64) -> Ray {
+        let mut eye = self.center;
+        let mut direction = direction;
+        let sideways = Vec3::cross(&direction, &self.v);
+        if eye_offset != 0.0 && sideways.length() > 0.0 {
+            eye = self.center + eye_offset * sideways.normalize();
+            direction = (self.center + self.convergence_distance * direction - eye).normalize();
         }
+
+        if self.defocus_angle <= 0.0 {
+            return Ray::from(eye, direction, ray_time);
+        }
+        let focus_point = eye + self.focus_distance * direction;
+        let lens = Onb::from_w(&direction);
+        let (x, y) = self.sample_aperture();
+        let ray_origin = eye + self.defocus_radius * (x * lens.u() + y * lens.v());
+        Ray::from(ray_origin, focus_point - ray_origin, ray_time)
+    }
+
+    /// Returns a random time during the exposure of image row `j`.
+    ///
+    /// With a rolling shutter each row's exposure starts a little later than the one above it.
+    fn sample_time(&self, j: usize) -> f64 {
+        let fraction = self.shutter_curve.sample(random_number());
+        let row_delay = self.rolling_shutter_duration * (j as f64 + 0.5) / self.image_height as f64;
+        self.shutter_open + row_delay + fraction * (self.shutter_close - self.shutter_open)
     }
 
-    fn get_ray(&self, i: usize, j: usize) -> Ray {
-        // gets randomly sampled ray for pixel at (i, j)
-        let pixel_center =
-            self.pixel_origin + (i as f64 * self.pixel_delta_u) + (j as f64 * self.pixel_delta_v);
-        let pixel_sample = pixel_center + self.pixel_sample_square();
+    /// Returns a random offset within the camera's aperture, or `None` if mechanical vignetting
+    /// blocks it for the pixel at `film_position`.
+    ///
+    /// The lens barrel is modeled as a second circle the size of the aperture, which slides
+    /// toward the edge of the frame the further the pixel is from its center. Only points inside
+    /// both pass, cutting the bokeh into a cat's eye and darkening the corners.
+    fn sample_defocus_disc(&self, film_position: (f64, f64)) -> Option<Vec3> {
+        let (x, y) = self.sample_aperture();
+        let (barrel_x, barrel_y) = (
+            self.cat_eye * film_position.0,
+            self.cat_eye * film_position.1,
+        );
+        if (x - barrel_x).powi(2) + (y - barrel_y).powi(2) > 1.0 {
+            return None;
+        }
+        Some(x * self.defocus_disc_u + y * self.defocus_disc_v)
+    }
 
-        let ray_origin = match self.defocus_angle <= 0.0 {
-            true => self.center,
-            _ => self.sample_defocus_disc(),
+    /// Returns a random point on the aperture, which fits in the unit disc.
+    ///
+    /// The shape comes from `aperture_image` if there is one, else from `aperture_blades`: a
+    /// regular polygon is sampled by picking one of its identical triangular wedges at random.
+    fn sample_aperture(&self) -> (f64, f64) {
+        let (x, y) = if let Some(distribution) = &self.aperture_distribution {
+            let (u, v) = random_pair();
+            let ((x, y), _) = distribution.sample(u, v);
+            (2.0 * x - 1.0, 1.0 - 2.0 * y)
+        } else if self.aperture_blades >= 3 {
+            // The first number picks the wedge, and what is left of it places the point.
+            let blades = self.aperture_blades as f64;
+            let (u, along) = random_pair();
+            let wedge = ((u * blades) as usize).min(self.aperture_blades - 1);
+            let start_angle = 2.0 * PI * wedge as f64 / blades;
+            let end_angle = 2.0 * PI * (wedge + 1) as f64 / blades;
+            let radius = (u * blades - wedge as f64).sqrt();
+            (
+                radius * ((1.0 - along) * start_angle.cos() + along * end_angle.cos()),
+                radius * ((1.0 - along) * start_angle.sin() + along * end_angle.sin()),
+            )
+        } else {
+            let point: Point3 = Vec3::random_unit_disk_point();
+            (point.x(), point.y())
         };
-        let ray_direction = pixel_sample - ray_origin;
 
-        let ray_time: f64 = random_number();
-        return Ray::from(ray_origin, ray_direction, ray_time);
+        let rotation = degrees_to_radians(self.aperture_rotation);
+        (
+            x * rotation.cos() - y * rotation.sin(),
+            x * rotation.sin() + y * rotation.cos(),
+        )
     }
 
-    fn sample_defocus_disc(&self) -> Point3 {
-        // return random point in camera defocus disk
-        let point: Point3 = Vec3::random_unit_disk_point();
-        return self.center + point.x() * self.defocus_disc_u + point.y() * self.defocus_disc_v;
+    /// Maps a position on the image, in pixels, to coordinates running from the center of the
+    /// frame to 1 at its corners, with `y` pointing up.
+    fn film_position(&self, x: f64, y: f64) -> (f64, f64) {
+        let half_width = 0.5 * self.image_width as f64;
+        let half_height = 0.5 * self.image_height as f64;
+        let half_diagonal = f64::sqrt(half_width * half_width + half_height * half_height);
+        (
+            (x - half_width) / half_diagonal,
+            (half_height - y) / half_diagonal,
+        )
     }
 
-    fn pixel_sample_square(&self) -> Vec3 {
-        // returns random point in square surrounding origin pixel
-        let px = -0.5 + random_number();
-        let py = -0.5 + random_number();
-        return (px * self.pixel_delta_u) + (py * self.pixel_delta_v);
+    /// Returns a random offset, in pixels, from the pixel center and the sample's filter weight.
+    fn sample_pixel_filter(&self) -> (f64, f64, f64) {
+        let sample = random_pair();
+        match &self.filter_sampler {
+            Some(filter_sampler) => filter_sampler.sample(sample),
+            None => (sample.0 - 0.5, sample.1 - 0.5, 1.0),
+        }
     }
 
     /// Initializes the camera properties based on the provided command-line arguments.
     fn initialize(&mut self) {
+        if let Some(animation) = self.animation.take() {
+            animation.apply(self, self.shutter_open);
+            self.animation = Some(animation);
+        }
+
         self.image_height = (self.image_width as f64 / self.aspect_ratio) as usize;
         self.image_height = self.image_height.max(1);
 
         self.center = self.camera_origin;
 
         // Calculate viewport dimension
-        let theta: f64 = degrees_to_radians(self.vfov);
-        let height_component = f64::tan(theta / 2.0);
-        let viewport_height = 2.0 * height_component * self.focus_distance as f64;
+        let viewport_height = match self.projection {
+            Projection::Perspective => {
+                let theta: f64 = degrees_to_radians(self.vfov);
+                let height_component = f64::tan(theta / 2.0);
+                2.0 * height_component * self.focus_distance as f64
+            }
+            Projection::Orthographic => self.view_height,
+            // The panoramic projections map pixels to directions without a viewport.
+            _ => 1.0,
+        };
         let viewport_width = viewport_height * self.image_width as f64 / self.image_height as f64;
 
         // Calculate basis vectors for camera coordinates
@@ -190,17 +639,51 @@ impl Camera {
         self.pixel_delta_u = viewport_u_vector / self.image_width as f64;
         self.pixel_delta_v = viewport_v_vector / self.image_height as f64;
 
-        // Calculate location of origin pixel [top left (0, 0)]
+        // Calculate location of origin pixel [top left (0, 0)], shifted with the lens. The
+        // perspective viewport lies in the plane of focus; the orthographic one at the camera.
+        let viewport_distance = match self.projection {
+            Projection::Orthographic => 0.0,
+            _ => self.focus_distance,
+        };
         let viewport_origin = self.center
-            - (self.focus_distance as f64 * self.w)
+            - (viewport_distance * self.w)
             - viewport_u_vector / 2.0
-            - viewport_v_vector / 2.0;
+            - viewport_v_vector / 2.0
+            + self.lens_shift.0 * viewport_u_vector
+            - self.lens_shift.1 * viewport_v_vector;
         self.pixel_origin = viewport_origin + 0.5 * (self.pixel_delta_u + self.pixel_delta_v);
         // Calculate camera defocus disk basis vectors
         let defocus_radius = self.focus_distance as f64
             * f64::tan(degrees_to_radians(self.defocus_angle as f64 / 2.0));
+        self.defocus_radius = defocus_radius;
         self.defocus_disc_u = self.u * defocus_radius;
         self.defocus_disc_v = self.v * defocus_radius;
+
+        // Tilting the plane of focus leans its normal toward the tilted directions
+        let (tilt, swing) = self.focal_tilt;
+        self.focal_plane_normal = (self.w
+            + f64::tan(degrees_to_radians(tilt)) * self.v
+            + f64::tan(degrees_to_radians(swing)) * self.u)
+            .normalize();
+
+        if let Some(lens_system) = self.lens_system.as_mut() {
+            lens_system.prepare(self.focus_distance, self.film_diagonal);
+        }
+
+        // Tabulate the aperture image so lens samples follow its brightness
+        self.aperture_distribution = self.aperture_image.as_ref().map(|image| {
+            let (width, height) = (image.width(), image.height());
+            let brightness: Vec<f64> = (0..width * height)
+                .map(|index| {
+                    let u = ((index % width) as f64 + 0.5) / width as f64;
+                    let v = 1.0 - ((index / width) as f64 + 0.5) / height as f64;
+                    image.scalar(u, v, &Point3::new())
+                })
+                .collect();
+            Distribution2D::from(width, height, &brightness)
+        });
+
+        self.filter_sampler = Some(FilterSampler::from(self.pixel_filter, self.filter_radius));
     }
 
     /// Renders the scene described by `world` to a PPM file.
@@ -211,34 +694,102 @@ impl Camera {
     ///
     /// * `world` - A `Traceables` object containing the objects in the scene.
     ///
-    /// After rendering, it outputs a file named `image.ppm` containing the result.
+    /// After rendering, it outputs the result to the PPM file at `output_path`.
 
     pub fn render(&mut self, world: Arc<Traceables>) {
+        // The animation poses the camera for this exposure only; keep the settings it
+        // overrides so the next render starts from them again.
+        let unanimated = (
+            self.camera_origin,
+            self.camera_target,
+            self.vfov,
+            self.focus_distance,
+        );
         self.initialize();
 
-        let mut file = File::create("images/image.ppm").expect("Could not create file `image.ppm`");
+        let mut file = File::create(&self.output_path)
+            .unwrap_or_else(|_| panic!("Could not create file `{}`", self.output_path));
+
+        // Stereo pairs are written as one image holding both eyes' views.
+        let (eyes_across, eyes_down) = self.stereo.layout();
+        let output_width = self.image_width * eyes_across;
+        let output_height = self.image_height * eyes_down;
 
-        writeln!(file, "P3\n{} {}\n255", self.image_width, self.image_height)
+        // Only pixels in the render region are traced; the file holds either just those or
+        // the whole image with everything else black.
+        let (x_start, y_start, x_end, y_end) = match self.render_region {
+            Some(region) => region.bounds(output_width, output_height),
+            None => (0, 0, output_width, output_height),
+        };
+        let (rows, columns) = match self.crop_output {
+            true => (y_start..y_end, x_start..x_end),
+            _ => (0..output_height, 0..output_width),
+        };
+
+        writeln!(file, "P3\n{} {}\n255", columns.len(), rows.len())
             .expect("Failed to write PPM header to file");
 
-        let total_scanlines = self.image_height;
+        let total_scanlines = rows.len();
         let scanlines_done = Arc::new(AtomicUsize::new(0));
 
+        // Key the samples to the exposure time too, so each frame of an animation gets its own
+        // noise instead of repeating the same pattern.
+        let sampler_seed = rng::hash(&[rng::seed(), self.shutter_open.to_bits()]);
+
         // Process each scanline in parallel
-        let pixels: Vec<_> = (0..self.image_height)
+        let pixels: Vec<_> = rows
             .into_par_iter()
-            .map(|j| {
-                let mut scanline_data = Vec::with_capacity(self.image_width);
+            .map(|output_j| {
+                let mut scanline_data = Vec::with_capacity(columns.len());
+                let previous_sampler = sampler::install(Some(
+                    self.sample_pattern
+                        .sampler(self.pixel_samples, sampler_seed),
+                ));
+
+                for output_i in columns.clone() {
+                    if !(x_start..x_end).contains(&output_i)
+                        || !(y_start..y_end).contains(&output_j)
+                    {
+                        scanline_data.push(write_color(Color::new(), 1));
+                        continue;
+                    }
+                    let (i, j) = (output_i % self.image_width, output_j % self.image_height);
+                    let eye = output_i / self.image_width + output_j / self.image_height;
+                    let eye_offset = self.stereo.eye_offset(eye, self.interocular_distance);
 
-                for i in 0..self.image_width {
+                    // Samples are averaged by their filter weights, which are negative in the
+                    // outer lobes of sharpening filters.
                     let mut pixel_color = Color::new();
-                    for _sample in 0..self.pixel_samples {
-                        let ray: Ray = self.get_ray(i, j);
-                        pixel_color += Self::get_ray_color(&ray, self.max_depth, &world);
+                    let mut weight_sum = 0.0;
+                    for sample in 0..self.pixel_samples {
+                        sampler::start_pixel_sample((output_i, output_j), sample);
+                        let (px, py, weight) = self.sample_pixel_filter();
+                        weight_sum += weight;
+                        let Some((ray, ray_weight)) = self.get_ray(i, j, (px, py), eye_offset)
+                        else {
+                            continue;
+                        };
+                        let weight = weight * ray_weight;
+                        let mut ray = self.follow_animation(ray);
+                        if !self.spectral {
+                            pixel_color += weight * self.get_ray_color(&ray, &world);
+                            continue;
+                        }
+                        // Follow the path at a random set of wavelengths and convert the
+                        // result back to RGB.
+                        let wavelengths = Wavelengths::sample(random_number());
+                        ray.set_wavelengths(Some(wavelengths));
+                        pixel_color +=
+                            weight * wavelengths.to_rgb(self.get_ray_color(&ray, &world));
+                    }
+                    if weight_sum <= 0.0 {
+                        pixel_color = Color::new();
+                        weight_sum = 1.0;
                     }
                     // Convert the pixel color to a string in PPM format.
-                    scanline_data.push(write_color(pixel_color, self.pixel_samples));
+                    scanline_data.push(write_color(pixel_color / weight_sum, 1));
                 }
+                sampler::install(previous_sampler);
 
                 // Update progress
                 let completed_scanlines = scanlines_done.fetch_add(1, Ordering::SeqCst) + 1;
@@ -269,7 +820,208 @@ impl Camera {
                     .expect("Failed to write to file");
             }
         }
+        (
+            self.camera_origin,
+            self.camera_target,
+            self.vfov,
+            self.focus_distance,
+        ) = unanimated;
 
         eprintln!("\nDone.");
     }
+
+    /// Renders the animation frames in `frames` at `frame_rate` frames per second, one file each.
+    ///
+    /// Frame `n` is exposed from `n / frame_rate` for as long as the shutter is set to stay
+    /// open, so set it with `set_shutter_angle` first. Files are named after `output_path` with
+    /// its run of `#` replaced by the zero-padded frame number, such as `images/frame_####.ppm`.
+    pub fn render_sequence(
+        &mut self,
+        world: Arc<Traceables>,
+        frames: Range<usize>,
+        frame_rate: f64,
+    ) {
+        let (shutter_open, shutter_close) = (self.shutter_open, self.shutter_close);
+        let output_pattern = self.output_path.clone();
+
+        for frame in frames {
+            let frame_time = frame as f64 / frame_rate;
+            self.shutter_open = frame_time;
+            self.shutter_close = frame_time + (shutter_close - shutter_open);
+            self.output_path = frame_path(&output_pattern, frame);
+            eprintln!("\nFrame {} -> {}", frame, self.output_path);
+            self.render(world.clone());
+        }
+
+        self.shutter_open = shutter_open;
+        self.shutter_close = shutter_close;
+        self.output_path = output_pattern;
+    }
+}
+
+/// Replaces the first run of `#` in `pattern` with `frame`, zero-padded to the run's length.
+/// Without any `#`, the frame number is added before the file extension.
+fn frame_path(pattern: &str, frame: usize) -> String {
+    if let Some(start) = pattern.find('#') {
+        let width = pattern[start..].chars().take_while(|&c| c == '#').count();
+        return format!(
+            "{}{:0width$}{}",
+            &pattern[..start],
+            frame,
+            &pattern[start + width..],
+            width = width
+        );
+    }
+    match pattern.rfind('.') {
+        Some(dot) => format!("{}_{:04}{}", &pattern[..dot], frame, &pattern[dot..]),
+        None => format!("{}_{:04}", pattern, frame),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::animation::track::Interpolation;
+    use crate::drawable::sphere::Sphere;
+    use crate::materials::{dielectric::Dielectric, lambert::Lambertian};
+
+    fn scene() -> Arc<Traceables> {
+        let mut world = Traceables::new();
+        world.add(Arc::new(Sphere::from(
+            Point3::from(0.0, -1000.0, 0.0),
+            1000.0,
+            Arc::new(Lambertian::from(Color::from(0.5, 0.5, 0.5))),
+        )));
+        world.add(Arc::new(Sphere::from(
+            Point3::from(-0.6, 0.5, 0.0),
+            0.5,
+            Arc::new(Lambertian::from(Color::from(0.7, 0.3, 0.3))),
+        )));
+        world.add(Arc::new(Sphere::from(
+            Point3::from(0.6, 0.5, 0.0),
+            0.5,
+            Arc::new(Dielectric::from(1.5)),
+        )));
+        Arc::new(world)
+    }
+
+    /// Renders the test scene on a pool of `threads` threads and returns the image file.
+    fn render(pattern: SamplePattern, threads: usize, shutter_open: f64, name: &str) -> Vec<u8> {
+        let mut camera = Camera::new();
+        camera.aspect_ratio = 16.0 / 9.0;
+        camera.image_width = 32;
+        camera.pixel_samples = 4;
+        camera.max_depth = 6;
+        camera.camera_origin = Point3::from(0.0, 1.0, 4.0);
+        camera.camera_target = Point3::from(0.0, 0.5, 0.0);
+        camera.focus_distance = 4.0;
+        camera.vfov = 40.0;
+        camera.defocus_angle = 1.0;
+        camera.sample_pattern = pattern;
+        camera.shutter_open = shutter_open;
+        camera.output_path = env::temp_dir()
+            .join(format!("ray-tracing-{}-{}.ppm", name, threads))
+            .to_string_lossy()
+            .into_owned();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let world = scene();
+        pool.install(|| camera.render(world));
+        let image = std::fs::read(&camera.output_path).unwrap();
+        std::fs::remove_file(&camera.output_path).unwrap();
+        image
+    }
+
+    #[test]
+    fn render_is_independent_of_thread_count() {
+        for (pattern, name) in [
+            (SamplePattern::Independent, "independent"),
+            (SamplePattern::Stratified, "stratified"),
+            (SamplePattern::Halton, "halton"),
+            (SamplePattern::Sobol, "sobol"),
+        ] {
+            let single = render(pattern, 1, 0.0, name);
+            let parallel = render(pattern, 4, 0.0, name);
+            assert!(
+                single == parallel,
+                "{:?} render depends on the thread count",
+                pattern
+            );
+        }
+    }
+
+    #[test]
+    fn frames_get_their_own_noise() {
+        let first = render(SamplePattern::Sobol, 2, 0.0, "frame-0");
+        let second = render(SamplePattern::Sobol, 2, 1.0, "frame-1");
+        assert!(first != second);
+    }
+
+    #[test]
+    fn orthographic_rays_ignore_focus_distance() {
+        let rays = [0.0, 2.0, 9.0].map(|focus_distance| {
+            let mut camera = Camera::new();
+            camera.projection = Projection::Orthographic;
+            camera.image_width = 40;
+            camera.camera_origin = Point3::from(1.0, 2.0, 3.0);
+            camera.camera_target = Point3::new();
+            camera.focus_distance = focus_distance;
+            camera.initialize();
+            (
+                camera.get_ray(7, 11, (0.25, 0.75), 0.0).unwrap().0,
+                camera.w,
+            )
+        });
+        for (ray, w) in rays.iter() {
+            let (reference, _) = &rays[0];
+            assert!((ray.origin() - reference.origin()).length() < 1e-12);
+            assert!((ray.direction() + *w).length() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn animation_leaves_camera_settings_alone() {
+        let mut animation = CameraAnimation::new();
+        animation
+            .camera_origin
+            .add_key(0.0, Point3::from(0.0, 1.0, 4.0), Interpolation::Linear);
+        animation
+            .camera_origin
+            .add_key(1.0, Point3::from(2.0, 1.0, 4.0), Interpolation::Linear);
+        animation.vfov.add_key(0.0, 30.0, Interpolation::Linear);
+        animation
+            .focus_distance
+            .add_key(0.0, 5.0, Interpolation::Linear);
+
+        let mut camera = Camera::new();
+        camera.image_width = 8;
+        camera.pixel_samples = 1;
+        camera.camera_origin = Point3::from(0.0, 0.0, 10.0);
+        camera.vfov = 60.0;
+        camera.focus_distance = 10.0;
+        camera.animation = Some(animation);
+        camera.output_path = env::temp_dir()
+            .join("ray-tracing-animated-#.ppm")
+            .to_string_lossy()
+            .into_owned();
+        camera.render_sequence(scene(), 0..2, 2.0);
+        for frame in 0..2 {
+            std::fs::remove_file(frame_path(&camera.output_path, frame)).unwrap();
+        }
+
+        assert!((camera.camera_origin - Point3::from(0.0, 0.0, 10.0)).length() == 0.0);
+        assert_eq!((camera.vfov, camera.focus_distance), (60.0, 10.0));
+    }
+
+    #[test]
+    fn frame_paths() {
+        assert_eq!(frame_path("out/frame_####.ppm", 7), "out/frame_0007.ppm");
+        assert_eq!(frame_path("f#.ppm", 12), "f12.ppm");
+        assert_eq!(frame_path("a##_b##.ppm", 3), "a03_b##.ppm");
+        assert_eq!(frame_path("out/frame.ppm", 42), "out/frame_0042.ppm");
+        assert_eq!(frame_path("frame", 5), "frame_0005");
+    }
 }
@@ -0,0 +1,458 @@
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::materials::fresnel::refract;
+use crate::math::rng::{self, Pcg32};
+use crate::math::rt_math::random_pair;
+use crate::ray::Ray;
+use crate::vector::{dot, Point3, Vec3};
+use rayon::prelude::*;
+
+/// Number of rings the film is split into when bounding the exit pupil.
+const EXIT_PUPIL_BINS: usize = 64;
+/// Number of rays traced per ring when bounding the exit pupil.
+const EXIT_PUPIL_SAMPLES: usize = 1 << 16;
+
+/// One surface of a lens prescription, in millimeters as lens tables are usually written.
+#[derive(Clone, Copy, Debug)]
+pub struct LensElement {
+    /// Radius of curvature; positive when the center lies toward the film, 0 for the aperture stop
+    pub curvature_radius: f64,
+    /// Distance along the axis to the next surface, or to the film for the last one
+    pub thickness: f64,
+    /// Index of refraction of the glass behind the surface; 0 or 1 for air
+    pub index_of_refraction: f64,
+    /// Diameter of the surface's clear aperture
+    pub aperture_diameter: f64,
+}
+
+impl LensElement {
+    /// Creates a lens surface from one row of a prescription table.
+    pub fn from(
+        curvature_radius: f64,
+        thickness: f64,
+        index_of_refraction: f64,
+        aperture_diameter: f64,
+    ) -> Self {
+        LensElement {
+            curvature_radius,
+            thickness,
+            index_of_refraction,
+            aperture_diameter,
+        }
+    }
+}
+
+/// A single lens surface converted to scene units (meters).
+#[derive(Clone, Copy, Debug)]
+struct Interface {
+    curvature_radius: f64,
+    thickness: f64,
+    eta: f64,
+    aperture_radius: f64,
+}
+
+/// A stack of spherical lens elements that camera rays are traced through, as in the realistic
+/// camera of PBRT.
+///
+/// Elements are listed from the front of the lens, facing the scene, to the back, facing the film.
+/// Tracing real rays through them gives the lens its own distortion, vignetting, focus breathing
+/// and depth of field. Lengths in prescriptions are millimeters, and the scene is assumed to be
+/// in meters.
+///
+/// The lens works in its own frame, looking down `+z` with the film on the `z = 0` plane.
+#[derive(Clone, Debug)]
+pub struct LensSystem {
+    interfaces: Vec<Interface>,
+    /// Bounds `(min_x, min_y, max_x, max_y)` of the rear element points that let light through,
+    /// for rings of film at increasing distance from the axis.
+    exit_pupil_bounds: Vec<(f64, f64, f64, f64)>,
+    film_diagonal: f64,
+    /// Distance from the rear element to the film in the prescription, before focusing.
+    rear_thickness: f64,
+    /// The focus distance and film diagonal the lens was last prepared for.
+    prepared_for: Option<(f64, f64)>,
+}
+
+impl LensSystem {
+    /// Creates a lens from its elements, front to back.
+    pub fn from(elements: &[LensElement]) -> Self {
+        let interfaces: Vec<Interface> = elements
+            .iter()
+            .map(|element| Interface {
+                curvature_radius: 0.001 * element.curvature_radius,
+                thickness: 0.001 * element.thickness,
+                eta: element.index_of_refraction,
+                aperture_radius: 0.001 * element.aperture_diameter / 2.0,
+            })
+            .collect();
+        let rear_thickness = interfaces.last().map_or(0.0, |last| last.thickness);
+        LensSystem {
+            interfaces,
+            exit_pupil_bounds: Vec::new(),
+            film_diagonal: 0.0,
+            rear_thickness,
+            prepared_for: None,
+        }
+    }
+
+    /// Loads a lens prescription in PBRT's text format.
+    ///
+    /// Each line holds the curvature radius, thickness, index of refraction and aperture
+    /// diameter of one surface, separated by whitespace; `#` starts a comment.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut elements = Vec::new();
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("");
+            if line.trim().is_empty() {
+                continue;
+            }
+            let values: Vec<f64> = line
+                .split_whitespace()
+                .map(|value| value.parse::<f64>())
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|_| Error::new(ErrorKind::InvalidData, "bad number in lens file"))?;
+            if values.len() != 4 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "lens elements need four values",
+                ));
+            }
+            elements.push(LensElement::from(
+                values[0], values[1], values[2], values[3],
+            ));
+        }
+        if elements.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "lens file is empty"));
+        }
+        Ok(LensSystem::from(&elements))
+    }
+
+    /// A 50mm f/2 double Gauss lens (US patent 2,673,491), the standard normal lens.
+    pub fn double_gauss() -> Self {
+        LensSystem::from(&[
+            LensElement::from(29.475, 3.76, 1.67, 25.2),
+            LensElement::from(84.83, 0.12, 1.0, 25.2),
+            LensElement::from(19.275, 4.025, 1.67, 23.0),
+            LensElement::from(40.77, 3.275, 1.699, 23.0),
+            LensElement::from(12.75, 5.705, 1.0, 18.0),
+            LensElement::from(0.0, 4.5, 0.0, 17.1),
+            LensElement::from(-14.495, 1.18, 1.603, 17.0),
+            LensElement::from(40.77, 6.065, 1.658, 20.0),
+            LensElement::from(-20.385, 0.19, 1.0, 20.0),
+            LensElement::from(437.065, 3.22, 1.717, 20.0),
+            LensElement::from(-39.73, 5.0, 1.0, 20.0),
+        ])
+    }
+
+    /// Stops the lens down (or opens it up) to an aperture stop `diameter` millimeters wide.
+    ///
+    /// The stop cannot open wider than the prescription allows.
+    pub fn with_aperture_diameter(mut self, diameter: f64) -> Self {
+        for interface in self.interfaces.iter_mut() {
+            if interface.curvature_radius == 0.0 {
+                interface.aperture_radius = interface.aperture_radius.min(0.001 * diameter / 2.0);
+            }
+        }
+        self.prepared_for = None;
+        self
+    }
+
+    /// Returns the distance from the film to the rear element.
+    fn rear_z(&self) -> f64 {
+        self.interfaces.last().map_or(0.0, |last| last.thickness)
+    }
+
+    /// Returns the distance from the film to the front element.
+    fn front_z(&self) -> f64 {
+        self.interfaces
+            .iter()
+            .map(|interface| interface.thickness)
+            .sum()
+    }
+
+    fn rear_radius(&self) -> f64 {
+        self.interfaces
+            .last()
+            .map_or(0.0, |last| last.aperture_radius)
+    }
+
+    /// Moves the lens so that objects `distance` meters from the film are in focus, and
+    /// prepares it for a film with the given diagonal in millimeters.
+    ///
+    /// Focusing uses a thick lens approximation of the whole system, found by tracing rays
+    /// parallel to the axis from either side. Bounding the exit pupil takes a while, so nothing
+    /// is redone when neither the distance nor the film has changed since the last call.
+    pub fn prepare(&mut self, distance: f64, film_diagonal: f64) {
+        if self.prepared_for == Some((distance, film_diagonal)) {
+            return;
+        }
+        self.prepared_for = Some((distance, film_diagonal));
+        self.film_diagonal = 0.001 * film_diagonal;
+        if let Some(last) = self.interfaces.last_mut() {
+            last.thickness = self.rear_thickness;
+        }
+        if let Some(delta) = self.focus_delta(distance) {
+            if let Some(last) = self.interfaces.last_mut() {
+                last.thickness += delta;
+            }
+        }
+        let bin_width = self.film_diagonal / 2.0 / EXIT_PUPIL_BINS as f64;
+        self.exit_pupil_bounds = (0..EXIT_PUPIL_BINS)
+            .into_par_iter()
+            .map(|bin| {
+                // Give each bin its own generator so the bounds do not depend on the thread pool
+                let mut generator = Pcg32::from(rng::seed(), bin as u64);
+                self.bound_exit_pupil(
+                    bin as f64 * bin_width,
+                    (bin + 1) as f64 * bin_width,
+                    &mut generator,
+                )
+            })
+            .collect();
+    }
+
+    /// Returns how far the rear element must move away from the film to focus at `distance`.
+    fn focus_delta(&self, distance: f64) -> Option<f64> {
+        let offset = 0.001 * self.film_diagonal;
+        let scene_ray = Ray::from(
+            Point3::from(offset, 0.0, self.front_z() + 1.0),
+            Vec3::from(0.0, 0.0, -1.0),
+            0.0,
+        );
+        let film_ray = self.trace_from_scene(&scene_ray)?;
+        let (principal_scene, focal_scene) = cardinal_points(&scene_ray, &film_ray);
+
+        let film_ray = Ray::from(
+            Point3::from(offset, 0.0, self.rear_z() - 1.0),
+            Vec3::from(0.0, 0.0, 1.0),
+            0.0,
+        );
+        let scene_ray = self.trace_from_film(&film_ray)?;
+        let (principal_film, _) = cardinal_points(&film_ray, &scene_ray);
+
+        let focal_length = focal_scene - principal_scene;
+        let z = -distance;
+        let c = (principal_film - z - principal_scene)
+            * (principal_film - z - 4.0 * focal_length - principal_scene);
+        if c < 0.0 {
+            return None;
+        }
+        Some(0.5 * (principal_film - z + principal_scene - c.sqrt()))
+    }
+
+    /// Finds a box on the rear element containing every point that light from film positions
+    /// between `start` and `end` on the `x` axis can pass through, trying points drawn from
+    /// `generator`.
+    fn bound_exit_pupil(
+        &self,
+        start: f64,
+        end: f64,
+        generator: &mut Pcg32,
+    ) -> (f64, f64, f64, f64) {
+        let rear_bound = 1.5 * self.rear_radius();
+        let mut bounds = (
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+        );
+        for sample in 0..EXIT_PUPIL_SAMPLES {
+            let along = (sample as f64 + 0.5) / EXIT_PUPIL_SAMPLES as f64;
+            let film_point = Point3::from(start + along * (end - start), 0.0, 0.0);
+            let x = (2.0 * generator.next_f64() - 1.0) * rear_bound;
+            let y = (2.0 * generator.next_f64() - 1.0) * rear_bound;
+            let inside = bounds.0 <= x && x <= bounds.2 && bounds.1 <= y && y <= bounds.3;
+            let rear_point = Point3::from(x, y, self.rear_z());
+            if inside
+                || self
+                    .trace_from_film(&Ray::from(film_point, rear_point - film_point, 0.0))
+                    .is_some()
+            {
+                bounds = (
+                    bounds.0.min(x),
+                    bounds.1.min(y),
+                    bounds.2.max(x),
+                    bounds.3.max(y),
+                );
+            }
+        }
+        if bounds.0 > bounds.2 {
+            return (-rear_bound, -rear_bound, rear_bound, rear_bound);
+        }
+        let expand = 2.0 * f64::sqrt(8.0) * rear_bound / (EXIT_PUPIL_SAMPLES as f64).sqrt();
+        (
+            bounds.0 - expand,
+            bounds.1 - expand,
+            bounds.2 + expand,
+            bounds.3 + expand,
+        )
+    }
+
+    /// Traces a ray leaving the film at `film_point` through the lens, returning the ray that
+    /// comes out of the front with the weight of the light it brings back, or `None` if the
+    /// lens blocks it.
+    ///
+    /// The rear element point is picked uniformly within the bounds of the exit pupil. As in
+    /// PBRT's `RealisticCamera`, the weight is cos⁴θ, the natural falloff of light reaching the
+    /// film at an angle, divided by the probability density of the point. It is measured
+    /// relative to the pupil at the center of the film, whose rays straight down the axis
+    /// have a weight of 1.
+    pub fn generate_ray(&self, film_point: (f64, f64)) -> Option<(Ray, f64)> {
+        let (x, y) = film_point;
+        let film_radius = f64::sqrt(x * x + y * y);
+        let bin = ((film_radius / (self.film_diagonal / 2.0) * EXIT_PUPIL_BINS as f64) as usize)
+            .min(EXIT_PUPIL_BINS - 1);
+        let bounds = self.exit_pupil_bounds[bin];
+        let (u, v) = random_pair();
+        let pupil_x = bounds.0 + u * (bounds.2 - bounds.0);
+        let pupil_y = bounds.1 + v * (bounds.3 - bounds.1);
+
+        // The bounds are for film points on the x axis; rotate them around to this one.
+        let (sin_theta, cos_theta) = match film_radius > 0.0 {
+            true => (y / film_radius, x / film_radius),
+            _ => (0.0, 1.0),
+        };
+        let rear_point = Point3::from(
+            cos_theta * pupil_x - sin_theta * pupil_y,
+            sin_theta * pupil_x + cos_theta * pupil_y,
+            self.rear_z(),
+        );
+        let film_point = Point3::from(x, y, 0.0);
+        let film_ray = Ray::from(film_point, rear_point - film_point, 0.0);
+
+        let cos_theta = film_ray.direction().normalize().z();
+        let weight =
+            cos_theta.powi(4) * bounds_area(bounds) / bounds_area(self.exit_pupil_bounds[0]);
+        Some((self.trace_from_film(&film_ray)?, weight))
+    }
+
+    /// Traces a ray from the film side through every element to the front of the lens.
+    fn trace_from_film(&self, ray: &Ray) -> Option<Ray> {
+        let mut origin = flip_z(&ray.origin());
+        let mut direction = flip_z(&ray.direction());
+        let mut element_z = 0.0;
+        for (i, interface) in self.interfaces.iter().enumerate().rev() {
+            element_z -= interface.thickness;
+            let (t, normal) = intersect_interface(interface, element_z, &origin, &direction)?;
+            origin += t * direction;
+            if origin.x() * origin.x() + origin.y() * origin.y()
+                > interface.aperture_radius * interface.aperture_radius
+            {
+                return None;
+            }
+            if let Some(normal) = normal {
+                let eta_i = air_or(interface.eta);
+                let eta_t = match i {
+                    0 => 1.0,
+                    _ => air_or(self.interfaces[i - 1].eta),
+                };
+                direction = refract(&-direction.normalize(), &normal, eta_t / eta_i)?;
+            }
+        }
+        Some(Ray::from(flip_z(&origin), flip_z(&direction), 0.0))
+    }
+
+    /// Traces a ray from the scene side through every element to the back of the lens.
+    fn trace_from_scene(&self, ray: &Ray) -> Option<Ray> {
+        let mut origin = flip_z(&ray.origin());
+        let mut direction = flip_z(&ray.direction());
+        let mut element_z = -self.front_z();
+        for (i, interface) in self.interfaces.iter().enumerate() {
+            let (t, normal) = intersect_interface(interface, element_z, &origin, &direction)?;
+            origin += t * direction;
+            if origin.x() * origin.x() + origin.y() * origin.y()
+                > interface.aperture_radius * interface.aperture_radius
+            {
+                return None;
+            }
+            if let Some(normal) = normal {
+                let eta_i = match i {
+                    0 => 1.0,
+                    _ => air_or(self.interfaces[i - 1].eta),
+                };
+                let eta_t = air_or(interface.eta);
+                direction = refract(&-direction.normalize(), &normal, eta_t / eta_i)?;
+            }
+            element_z += interface.thickness;
+        }
+        Some(Ray::from(flip_z(&origin), flip_z(&direction), 0.0))
+    }
+}
+
+/// Returns the area of exit pupil bounds `(min_x, min_y, max_x, max_y)`.
+fn bounds_area(bounds: (f64, f64, f64, f64)) -> f64 {
+    (bounds.2 - bounds.0) * (bounds.3 - bounds.1)
+}
+
+/// Intersects a ray, in lens space, with an interface whose vertex is at `element_z`.
+///
+/// Returns the ray parameter of the hit and, unless the interface is the flat aperture stop,
+/// the surface normal facing back along the ray.
+fn intersect_interface(
+    interface: &Interface,
+    element_z: f64,
+    origin: &Point3,
+    direction: &Vec3,
+) -> Option<(f64, Option<Vec3>)> {
+    let radius = interface.curvature_radius;
+    if radius == 0.0 {
+        let t = (element_z - origin.z()) / direction.z();
+        return match t >= 0.0 {
+            true => Some((t, None)),
+            _ => None,
+        };
+    }
+
+    let offset = *origin - Vec3::from(0.0, 0.0, element_z + radius);
+    let a = dot(direction, direction);
+    let b = 2.0 * dot(direction, &offset);
+    let c = dot(&offset, &offset) - radius * radius;
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let q = match b < 0.0 {
+        true => -0.5 * (b - root),
+        _ => -0.5 * (b + root),
+    };
+    let (t0, t1) = (q / a, c / q);
+    let (t0, t1) = (t0.min(t1), t0.max(t1));
+
+    // Which of the two hits is on the lens depends on whether the surface bulges toward the ray.
+    let use_closer = (direction.z() > 0.0) != (radius < 0.0);
+    let t = if use_closer { t0 } else { t1 };
+    if t < 0.0 {
+        return None;
+    }
+    let mut normal = (offset + t * *direction).normalize();
+    if dot(&normal, direction) > 0.0 {
+        normal = -normal;
+    }
+    Some((t, Some(normal)))
+}
+
+/// Finds where a ray parallel to the axis crosses it after passing through the lens (the focal
+/// point) and where its entering and leaving lines meet (the principal plane), as `z` values.
+fn cardinal_points(ray_in: &Ray, ray_out: &Ray) -> (f64, f64) {
+    let focal = -ray_out.origin().x() / ray_out.direction().x();
+    let focal_z = -ray_out.at(focal).z();
+    let principal = (ray_in.origin().x() - ray_out.origin().x()) / ray_out.direction().x();
+    let principal_z = -ray_out.at(principal).z();
+    (principal_z, focal_z)
+}
+
+fn flip_z(vector: &Vec3) -> Vec3 {
+    Vec3::from(vector.x(), vector.y(), -vector.z())
+}
+
+/// Treats an index of refraction of 0, which prescriptions use for the stop, as air.
+fn air_or(eta: f64) -> f64 {
+    match eta == 0.0 {
+        true => 1.0,
+        _ => eta,
+    }
+}
@@ -1 +1,7 @@
 pub mod camera;
+pub mod filter;
+pub mod lens_system;
+pub mod projection;
+pub mod region;
+pub mod shutter;
+pub mod stereo;
@@ -0,0 +1,74 @@
+use crate::math::rt_math::{degrees_to_radians, PI};
+use crate::vector::Vec3;
+
+/// How the camera maps points in the scene onto the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// Rays fan out from the camera origin, covering `vfov`.
+    Perspective,
+    /// Rays run parallel to the view direction, covering `view_height` world units vertically.
+    ///
+    /// Rays start on the plane through `camera_origin`, so anything behind it is not seen.
+    Orthographic,
+    /// A 360 by 180 degree latitude-longitude panorama, for viewing in 360 viewers.
+    ///
+    /// Use an `aspect_ratio` of 2 to get square pixels.
+    Equirectangular,
+    /// A circular fisheye image covering `fisheye_fov`.
+    Fisheye(FisheyeMapping),
+}
+
+/// How a fisheye lens maps the angle from its axis to distance from the image center.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FisheyeMapping {
+    /// Distance proportional to angle, as used for dome projection.
+    Equidistant,
+    /// Equal solid angles cover equal image areas, as in most photographic fisheyes.
+    Equisolid,
+}
+
+impl FisheyeMapping {
+    /// Maps a position `(x, y)` on a `width` by `height` image, in pixels, to a direction
+    /// through the fisheye lens, in the camera's `(u, v, w)` basis.
+    ///
+    /// The image circle spans `fov` degrees and fills the shorter side of the image; positions
+    /// outside it return `None`.
+    pub fn direction(self, x: f64, y: f64, width: usize, height: usize, fov: f64) -> Option<Vec3> {
+        let radius = 0.5 * width.min(height) as f64;
+        let film_x = (x - 0.5 * width as f64) / radius;
+        let film_y = (0.5 * height as f64 - y) / radius;
+        let film_radius = f64::sqrt(film_x * film_x + film_y * film_y);
+        if film_radius > 1.0 {
+            return None;
+        }
+
+        let max_theta = degrees_to_radians(fov / 2.0);
+        let theta = match self {
+            FisheyeMapping::Equidistant => film_radius * max_theta,
+            FisheyeMapping::Equisolid => {
+                2.0 * f64::asin((film_radius * f64::sin(max_theta / 2.0)).min(1.0))
+            }
+        };
+        let azimuth = f64::atan2(film_y, film_x);
+        Some(Vec3::from(
+            theta.sin() * azimuth.cos(),
+            theta.sin() * azimuth.sin(),
+            -theta.cos(),
+        ))
+    }
+}
+
+/// Maps a position `(x, y)` on a `width` by `height` image, in pixels, to a direction on the
+/// latitude-longitude sphere, in the camera's `(u, v, w)` basis.
+///
+/// The image spans 360 degrees horizontally and 180 vertically, with the view direction in
+/// the middle, so it should be twice as wide as it is tall.
+pub fn equirectangular_direction(x: f64, y: f64, width: usize, height: usize) -> Vec3 {
+    let longitude = (x / width as f64 - 0.5) * 2.0 * PI;
+    let latitude = (0.5 - y / height as f64) * PI;
+    Vec3::from(
+        latitude.cos() * longitude.sin(),
+        latitude.sin(),
+        -latitude.cos() * longitude.cos(),
+    )
+}
@@ -0,0 +1,138 @@
+/// A rectangle of the output image to render, leaving the rest out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderRegion {
+    /// A region in pixels, from the top-left corner of the image.
+    Pixels {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    /// A region in fractions of the image size, from `(0, 0)` at the top left to `(1, 1)` at
+    /// the bottom right. Edges are rounded to the nearest pixel, so neighboring regions
+    /// sharing an edge never overlap or leave a gap.
+    Normalized {
+        x_min: f64,
+        y_min: f64,
+        x_max: f64,
+        y_max: f64,
+    },
+}
+
+impl RenderRegion {
+    /// Returns tile (`column`, `row`) of the image split into a `columns` by `rows` grid, so a
+    /// large image can be rendered in pieces.
+    pub fn tile(column: usize, row: usize, columns: usize, rows: usize) -> Self {
+        RenderRegion::Normalized {
+            x_min: column as f64 / columns as f64,
+            y_min: row as f64 / rows as f64,
+            x_max: (column + 1) as f64 / columns as f64,
+            y_max: (row + 1) as f64 / rows as f64,
+        }
+    }
+
+    /// Returns the region as pixel bounds `(x_start, y_start, x_end, y_end)`, ends exclusive,
+    /// clipped to an image of the given size.
+    pub fn bounds(&self, width: usize, height: usize) -> (usize, usize, usize, usize) {
+        let (x_start, y_start, x_end, y_end) = match *self {
+            RenderRegion::Pixels {
+                x,
+                y,
+                width,
+                height,
+            } => (x, y, x.saturating_add(width), y.saturating_add(height)),
+            RenderRegion::Normalized {
+                x_min,
+                y_min,
+                x_max,
+                y_max,
+            } => (
+                (x_min.max(0.0) * width as f64).round() as usize,
+                (y_min.max(0.0) * height as f64).round() as usize,
+                (x_max.max(0.0) * width as f64).round() as usize,
+                (y_max.max(0.0) * height as f64).round() as usize,
+            ),
+        };
+        let (x_end, y_end) = (x_end.min(width), y_end.min(height));
+        (x_start.min(x_end), y_start.min(y_end), x_end, y_end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixel_regions_are_clipped_to_the_image() {
+        let region = RenderRegion::Pixels {
+            x: 50,
+            y: 10,
+            width: 100,
+            height: 20,
+        };
+        assert_eq!(region.bounds(120, 80), (50, 10, 120, 30));
+    }
+
+    #[test]
+    fn huge_pixel_regions_do_not_overflow() {
+        let region = RenderRegion::Pixels {
+            x: 5,
+            y: 5,
+            width: usize::MAX,
+            height: usize::MAX,
+        };
+        assert_eq!(region.bounds(40, 30), (5, 5, 40, 30));
+    }
+
+    #[test]
+    fn regions_outside_the_image_are_empty() {
+        let region = RenderRegion::Pixels {
+            x: 200,
+            y: 100,
+            width: 10,
+            height: 10,
+        };
+        let (x_start, y_start, x_end, y_end) = region.bounds(120, 80);
+        assert!(x_start == x_end && y_start == y_end);
+    }
+
+    #[test]
+    fn normalized_regions_round_to_the_nearest_pixel() {
+        let region = RenderRegion::Normalized {
+            x_min: 0.26,
+            y_min: 0.24,
+            x_max: 0.74,
+            y_max: 0.76,
+        };
+        assert_eq!(region.bounds(10, 10), (3, 2, 7, 8));
+    }
+
+    #[test]
+    fn normalized_regions_beyond_the_edges_are_clipped() {
+        let region = RenderRegion::Normalized {
+            x_min: -0.5,
+            y_min: -1.0,
+            x_max: 1.5,
+            y_max: 2.0,
+        };
+        assert_eq!(region.bounds(64, 48), (0, 0, 64, 48));
+    }
+
+    #[test]
+    fn tiles_cover_the_image_without_gaps_or_overlap() {
+        let (width, height, columns, rows) = (101, 37, 7, 3);
+        let mut covered = vec![0; width * height];
+        for row in 0..rows {
+            for column in 0..columns {
+                let (x_start, y_start, x_end, y_end) =
+                    RenderRegion::tile(column, row, columns, rows).bounds(width, height);
+                for y in y_start..y_end {
+                    for x in x_start..x_end {
+                        covered[y * width + x] += 1;
+                    }
+                }
+            }
+        }
+        assert!(covered.iter().all(|&count| count == 1));
+    }
+}
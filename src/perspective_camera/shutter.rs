@@ -0,0 +1,22 @@
+/// How the shutter's opening varies over the exposure, which shapes motion blur trails.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShutterCurve {
+    /// Fully open for the whole exposure, giving evenly bright trails.
+    Box,
+    /// Opens linearly to a peak at mid-exposure and closes again, giving trails that fade out.
+    Triangle,
+}
+
+impl ShutterCurve {
+    /// Maps a uniform random number `u` to a moment in the exposure, as a fraction from 0 at
+    /// opening to 1 at closing, drawn in proportion to how far open the shutter is.
+    pub fn sample(&self, u: f64) -> f64 {
+        match self {
+            ShutterCurve::Box => u,
+            ShutterCurve::Triangle => match u < 0.5 {
+                true => f64::sqrt(2.0 * u) / 2.0,
+                _ => 1.0 - f64::sqrt(2.0 * (1.0 - u)) / 2.0,
+            },
+        }
+    }
+}
@@ -0,0 +1,30 @@
+/// How left and right eye views are arranged in a stereoscopic image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StereoMode {
+    /// A single, monoscopic view.
+    Off,
+    /// The left eye's view on the left half and the right eye's on the right.
+    SideBySide,
+    /// The left eye's view on the top half and the right eye's on the bottom.
+    OverUnder,
+}
+
+impl StereoMode {
+    /// Returns how many eye views fit across and down the output image.
+    pub fn layout(&self) -> (usize, usize) {
+        match self {
+            StereoMode::Off => (1, 1),
+            StereoMode::SideBySide => (2, 1),
+            StereoMode::OverUnder => (1, 2),
+        }
+    }
+
+    /// Returns the sideways offset of eye number `eye` (0 left, 1 right) from the camera center,
+    /// for eyes `interocular_distance` apart.
+    pub fn eye_offset(&self, eye: usize, interocular_distance: f64) -> f64 {
+        match self {
+            StereoMode::Off => 0.0,
+            _ => (eye as f64 - 0.5) * interocular_distance,
+        }
+    }
+}
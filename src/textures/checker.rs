@@ -0,0 +1,34 @@
+use super::texture::Texture;
+use crate::{color::Color, vector::Point3};
+use std::sync::Arc;
+
+/// A solid 3D checkerboard alternating between two textures.
+pub struct CheckerTexture {
+    inverse_scale: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl CheckerTexture {
+    /// Creates a checkerboard whose cells are `scale` units wide.
+    pub fn from(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        CheckerTexture {
+            inverse_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, point: &Point3) -> Color {
+        let x = (self.inverse_scale * point.x()).floor() as i64;
+        let y = (self.inverse_scale * point.y()).floor() as i64;
+        let z = (self.inverse_scale * point.z()).floor() as i64;
+        if (x + y + z) % 2 == 0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use super::texture::Texture;
+use crate::{color::Color, vector::Point3};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+/// A texture backed by an image, looked up by surface coordinates.
+///
+/// Images tile in both directions and are filtered bilinearly, with `v = 0` at the bottom row.
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl ImageTexture {
+    /// Creates a texture from `width * height` linear colors stored row by row, top row first.
+    pub fn from(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        assert_eq!(pixels.len(), width * height, "pixel count must match size");
+        ImageTexture {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Loads a color image from a PPM file (`P3` or `P6`).
+    ///
+    /// Values are decoded with the same gamma of 2 the renderer encodes its output with, so a
+    /// rendered image read back as a texture reproduces the original colors.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut texture = Self::load_linear(path)?;
+        for pixel in texture.pixels.iter_mut() {
+            *pixel = *pixel * *pixel;
+        }
+        Ok(texture)
+    }
+
+    /// Loads a PPM file whose values are data rather than colors, such as a normal or bump map.
+    pub fn load_linear<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// Decodes the contents of a PPM file, without gamma decoding.
+    fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut position = 0;
+        let magic = next_token(bytes, &mut position)?;
+        let width = parse_number(next_token(bytes, &mut position)?)?;
+        let height = parse_number(next_token(bytes, &mut position)?)?;
+        let max_value = parse_number(next_token(bytes, &mut position)?)?;
+        if width == 0 || height == 0 || max_value == 0 || max_value > 65535 {
+            return Err(invalid_data("bad PPM header"));
+        }
+        let scale = 1.0 / max_value as f64;
+
+        let count = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or_else(|| invalid_data("PPM image too large"))?;
+        let samples: Vec<usize> = match magic {
+            b"P3" => (0..count)
+                .map(|_| parse_number(next_token(bytes, &mut position)?))
+                .collect::<Result<_>>()?,
+            b"P6" => {
+                // A single whitespace byte separates the header from the raster.
+                let raster = bytes.get(position + 1..).unwrap_or(&[]);
+                let sample_size = if max_value < 256 { 1 } else { 2 };
+                if raster.len() < count * sample_size {
+                    return Err(invalid_data("truncated PPM raster"));
+                }
+                raster
+                    .chunks_exact(sample_size)
+                    .take(count)
+                    .map(|sample| sample.iter().fold(0, |value, &b| (value << 8) | b as usize))
+                    .collect()
+            }
+            _ => return Err(invalid_data("not a PPM file")),
+        };
+        if samples.iter().any(|&sample| sample > max_value) {
+            return Err(invalid_data("PPM sample above maximum value"));
+        }
+
+        let pixels = samples
+            .chunks_exact(3)
+            .map(|rgb| {
+                Color::from(
+                    rgb[0] as f64 * scale,
+                    rgb[1] as f64 * scale,
+                    rgb[2] as f64 * scale,
+                )
+            })
+            .collect();
+        Ok(ImageTexture::from(width, height, pixels))
+    }
+
+    /// Returns the image width in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the image height in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn pixel(&self, x: i64, y: i64) -> Color {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.rem_euclid(self.height as i64) as usize;
+        self.pixels[y * self.width + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _point: &Point3) -> Color {
+        // Pixel centers sit at half-integer coordinates; rows are stored top first.
+        let x = u * self.width as f64 - 0.5;
+        let y = (1.0 - v) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = (1.0 - tx) * self.pixel(x0, y0) + tx * self.pixel(x0 + 1, y0);
+        let bottom = (1.0 - tx) * self.pixel(x0, y0 + 1) + tx * self.pixel(x0 + 1, y0 + 1);
+        (1.0 - ty) * top + ty * bottom
+    }
+}
+
+/// Returns the next whitespace-separated token of a PPM header, skipping `#` comments.
+fn next_token<'a>(bytes: &'a [u8], position: &mut usize) -> Result<&'a [u8]> {
+    loop {
+        while *position < bytes.len() && bytes[*position].is_ascii_whitespace() {
+            *position += 1;
+        }
+        if *position < bytes.len() && bytes[*position] == b'#' {
+            while *position < bytes.len() && bytes[*position] != b'\n' {
+                *position += 1;
+            }
+            continue;
+        }
+        break;
+    }
+    let start = *position;
+    while *position < bytes.len() && !bytes[*position].is_ascii_whitespace() {
+        *position += 1;
+    }
+    if start == *position {
+        return Err(invalid_data("unexpected end of PPM file"));
+    }
+    Ok(&bytes[start..*position])
+}
+
+fn parse_number(token: &[u8]) -> Result<usize> {
+    std::str::from_utf8(token)
+        .ok()
+        .and_then(|text| text.parse().ok())
+        .ok_or_else(|| invalid_data("bad number in PPM file"))
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(bytes: &[u8]) -> String {
+        match ImageTexture::parse(bytes) {
+            Ok(_) => panic!("parsed {:?}", String::from_utf8_lossy(bytes)),
+            Err(error) => {
+                assert_eq!(error.kind(), ErrorKind::InvalidData);
+                error.to_string()
+            }
+        }
+    }
+
+    #[test]
+    fn parses_ascii_and_binary_images() {
+        let ascii = ImageTexture::parse(b"P3\n# a comment\n2 1\n255\n255 0 0  0 51 255\n").unwrap();
+        let binary = ImageTexture::parse(b"P6 2 1 255\n\xff\x00\x00\x00\x33\xff").unwrap();
+        for texture in [ascii, binary] {
+            assert_eq!((texture.width(), texture.height()), (2, 1));
+            assert_eq!(texture.pixel(0, 0).x(), 1.0);
+            assert_eq!(texture.pixel(1, 0).y(), 0.2);
+        }
+    }
+
+    #[test]
+    fn parses_sixteen_bit_samples() {
+        let texture = ImageTexture::parse(b"P6 1 1 65535\n\x80\x00\x00\x00\xff\xff").unwrap();
+        assert_eq!(texture.pixel(0, 0).x(), 32768.0 / 65535.0);
+        assert_eq!(texture.pixel(0, 0).z(), 1.0);
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        assert_eq!(parse_error(b"P5 1 1 255\n\x00"), "not a PPM file");
+        assert_eq!(parse_error(b"GIF89a"), "unexpected end of PPM file");
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        assert_eq!(parse_error(b""), "unexpected end of PPM file");
+        assert_eq!(
+            parse_error(b"P3 2 # no height"),
+            "unexpected end of PPM file"
+        );
+        assert_eq!(parse_error(b"P3 2 x 255"), "bad number in PPM file");
+        assert_eq!(parse_error(b"P3 -2 1 255"), "bad number in PPM file");
+        assert_eq!(parse_error(b"P3 0 1 255"), "bad PPM header");
+        assert_eq!(parse_error(b"P3 1 1 0"), "bad PPM header");
+        assert_eq!(parse_error(b"P3 1 1 65536"), "bad PPM header");
+        let huge = format!("P6 {} {} 255\n", usize::MAX / 2, 3);
+        assert_eq!(parse_error(huge.as_bytes()), "PPM image too large");
+    }
+
+    #[test]
+    fn rejects_bad_rasters() {
+        assert_eq!(parse_error(b"P3 1 1 255 1 2"), "unexpected end of PPM file");
+        assert_eq!(parse_error(b"P3 1 1 255 1 2 z"), "bad number in PPM file");
+        assert_eq!(parse_error(b"P6 1 1 255\n\x01\x02"), "truncated PPM raster");
+        assert_eq!(
+            parse_error(b"P6 1 1 65535\n\x01\x02\x03"),
+            "truncated PPM raster"
+        );
+        assert_eq!(
+            parse_error(b"P3 1 1 100 50 101 0"),
+            "PPM sample above maximum value"
+        );
+    }
+}
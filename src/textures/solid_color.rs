@@ -0,0 +1,32 @@
+use super::texture::Texture;
+use crate::{color::Color, vector::Point3};
+
+/// A texture with the same value everywhere.
+pub struct SolidColor {
+    color: Color,
+}
+
+impl SolidColor {
+    pub fn new() -> Self {
+        SolidColor {
+            color: Color::new(),
+        }
+    }
+
+    pub fn from(color: Color) -> Self {
+        SolidColor { color }
+    }
+
+    /// Creates a grayscale texture holding a single scalar `value`.
+    pub fn from_value(value: f64) -> Self {
+        SolidColor {
+            color: Color::from(value, value, value),
+        }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _point: &Point3) -> Color {
+        self.color
+    }
+}
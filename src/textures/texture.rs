@@ -0,0 +1,18 @@
+use crate::{color::Color, vector::Point3};
+
+/// Trait for values that vary across a surface, such as colors or roughness.
+///
+/// Textures are looked up with the surface coordinates `(u, v)` of a hit and the hit point
+/// itself, so both image-like and solid (3D) patterns can be expressed.
+pub trait Texture: Send + Sync {
+    /// Returns the texture's color at surface coordinates `(u, v)` and position `point`.
+    fn value(&self, u: f64, v: f64, point: &Point3) -> Color;
+
+    /// Returns the texture's value as a single number, averaging the color channels.
+    ///
+    /// This is how grayscale textures drive scalar parameters such as roughness.
+    fn scalar(&self, u: f64, v: f64, point: &Point3) -> f64 {
+        let color = self.value(u, v, point);
+        (color.x() + color.y() + color.z()) / 3.0
+    }
+}
@@ -1,3 +1,4 @@
 pub mod color;
 pub mod ray;
+pub mod spectrum;
 pub mod vector;
@@ -1,5 +1,6 @@
 use std::fmt::Display;
 
+use crate::spectrum::Wavelengths;
 use crate::vector::*;
 
 /// Represents a ray in 3D space.
@@ -9,9 +10,10 @@ use crate::vector::*;
 /// Positive values of `t` correspond to points in front of the origin `A`, while negative values correspond to points behind the origin.
 #[derive(Clone, Copy)]
 pub struct Ray {
-    origin: Point3,  // origin coordinates
-    direction: Vec3, // direction vector
-    time: f64,       // random sample time for interpolation
+    origin: Point3,                   // origin coordinates
+    direction: Vec3,                  // direction vector
+    time: f64,                        // random sample time for interpolation
+    wavelengths: Option<Wavelengths>, // wavelengths carried in spectral mode
 }
 
 impl Display for Ray {
@@ -30,6 +32,7 @@ impl Ray {
             origin: Vec3::new(),
             direction: Vec3::new(),
             time: 0.0,
+            wavelengths: None,
         }
     }
 
@@ -48,6 +51,7 @@ impl Ray {
             origin,
             direction,
             time,
+            wavelengths: None,
         };
     }
 
@@ -85,4 +89,14 @@ impl Ray {
     pub fn time(&self) -> f64 {
         return self.time;
     }
+
+    /// Returns the wavelengths carried by the ray, if rendering in spectral mode.
+    pub fn wavelengths(&self) -> Option<Wavelengths> {
+        self.wavelengths
+    }
+
+    /// Sets the wavelengths carried by the ray.
+    pub fn set_wavelengths(&mut self, wavelengths: Option<Wavelengths>) {
+        self.wavelengths = wavelengths;
+    }
 }
@@ -0,0 +1,181 @@
+use crate::color::Color;
+use crate::ray::Ray;
+use std::sync::OnceLock;
+
+/// Shortest wavelength sampled in spectral mode, in nanometers.
+pub const LAMBDA_MIN: f64 = 380.0;
+/// Longest wavelength sampled in spectral mode, in nanometers.
+pub const LAMBDA_MAX: f64 = 780.0;
+
+/// The wavelengths carried by a path in spectral mode.
+///
+/// Each path follows a randomly chosen hero wavelength plus two companions spaced evenly
+/// across the visible range, one per channel of a `Color`. This lets all of the existing RGB
+/// arithmetic carry spectral values unchanged: in spectral mode the three channels of a path's
+/// color hold its value at these three wavelengths.
+///
+/// Reference: Wilkie et al., "Hero Wavelength Spectral Sampling" (EGSR 2014).
+#[derive(Clone, Copy)]
+pub struct Wavelengths {
+    lambda: [f64; 3],
+    secondary_terminated: bool,
+}
+
+impl Wavelengths {
+    /// Chooses a hero wavelength from the uniform random number `u` and places the companions
+    /// at equal offsets from it, wrapping around the visible range.
+    pub fn sample(u: f64) -> Self {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let hero = u * range;
+        let mut lambda = [0.0; 3];
+        for (i, wavelength) in lambda.iter_mut().enumerate() {
+            *wavelength = LAMBDA_MIN + (hero + i as f64 * range / 3.0) % range;
+        }
+        Wavelengths {
+            lambda,
+            secondary_terminated: false,
+        }
+    }
+
+    /// The wavelength the path's direction is chosen for, in nanometers.
+    pub fn hero(&self) -> f64 {
+        self.lambda[0]
+    }
+
+    /// Returns the wavelength carried in the given channel, in nanometers.
+    pub fn get(&self, channel: usize) -> f64 {
+        self.lambda[channel]
+    }
+
+    /// Indicates whether only the hero wavelength is still being carried.
+    pub fn secondary_terminated(&self) -> bool {
+        self.secondary_terminated
+    }
+
+    /// Drops the companion wavelengths, for when a material such as a dispersive prism sends
+    /// each wavelength in a different direction and only the hero can be followed.
+    ///
+    /// Returns the same wavelengths marked as terminated, along with the factor the path's
+    /// throughput must be multiplied by to stay unbiased.
+    pub fn terminate_secondary(&self) -> (Wavelengths, Color) {
+        let terminated = Wavelengths {
+            lambda: self.lambda,
+            secondary_terminated: true,
+        };
+        if self.secondary_terminated {
+            return (terminated, Color::from(1.0, 0.0, 0.0));
+        }
+        (terminated, Color::from(3.0, 0.0, 0.0))
+    }
+
+    /// Converts an RGB value to its spectral values at these wavelengths.
+    pub fn uplift(&self, rgb: Color) -> Color {
+        Color::from(
+            rgb_to_spectrum(rgb, self.lambda[0]),
+            rgb_to_spectrum(rgb, self.lambda[1]),
+            rgb_to_spectrum(rgb, self.lambda[2]),
+        )
+    }
+
+    /// Converts a path's spectral values at these wavelengths to a linear sRGB estimate.
+    ///
+    /// The result is white balanced so that a spectrally flat value comes out neutral.
+    pub fn to_rgb(self, spectral: Color) -> Color {
+        let pdf = 1.0 / (LAMBDA_MAX - LAMBDA_MIN);
+        let mut xyz = Color::new();
+        for channel in 0..3 {
+            xyz += color_matching(self.lambda[channel]) * (spectral[channel] / pdf);
+        }
+        xyz /= 3.0;
+        xyz_to_linear_srgb(&xyz) / equal_energy_white()
+    }
+}
+
+/// Converts `rgb` to the spectral value it corresponds to at `wavelength` when the ray carries
+/// wavelengths, and returns it unchanged otherwise.
+///
+/// Materials and lights call this on their RGB parameters so they work in both modes.
+pub fn uplift(ray: &Ray, rgb: Color) -> Color {
+    match ray.wavelengths() {
+        Some(wavelengths) => wavelengths.uplift(rgb),
+        None => rgb,
+    }
+}
+
+/// Converts a reflectance spectrum to RGB for rendering in RGB mode.
+///
+/// The spectrum is integrated against the color matching functions at evenly spaced
+/// wavelengths and white balanced, so a flat spectrum of value `r` becomes `(r, r, r)`.
+pub fn reflectance_to_rgb<F: Fn(f64) -> f64>(reflectance: F) -> Color {
+    const SAMPLES: usize = 32;
+    let step = (LAMBDA_MAX - LAMBDA_MIN) / SAMPLES as f64;
+    let mut xyz = Color::new();
+    for i in 0..SAMPLES {
+        let wavelength = LAMBDA_MIN + (i as f64 + 0.5) * step;
+        xyz += color_matching(wavelength) * (reflectance(wavelength) * step);
+    }
+    xyz_to_linear_srgb(&xyz) / equal_energy_white()
+}
+
+/// Evaluates a smooth spectrum for an RGB triple at a wavelength in nanometers.
+///
+/// The spectrum is a blend of three smooth basis curves covering the blue, green, and red
+/// parts of the visible range. The curves sum to one at every wavelength, so white maps to a
+/// flat spectrum and reflectances in `[0, 1]` stay in `[0, 1]`. Converting back to sRGB
+/// recovers the primaries to within a few percent without needing any lookup tables.
+pub fn rgb_to_spectrum(rgb: Color, wavelength: f64) -> f64 {
+    let blue = 1.0 - smoothstep(460.0, 520.0, wavelength);
+    let red = smoothstep(560.0, 620.0, wavelength);
+    let green = 1.0 - blue - red;
+    rgb.x() * red + rgb.y() * green + rgb.z() * blue
+}
+
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// CIE 1931 standard observer color matching functions at `wavelength`, as an XYZ triple.
+///
+/// Uses the multi-lobe Gaussian fit from Wyman, Sloan, Shirley, "Simple Analytic
+/// Approximations to the CIE XYZ Color Matching Functions" (JCGT 2013).
+pub fn color_matching(wavelength: f64) -> Color {
+    let lobe = |mean: f64, sigma_low: f64, sigma_high: f64| {
+        let sigma = if wavelength < mean {
+            sigma_low
+        } else {
+            sigma_high
+        };
+        let t = (wavelength - mean) / sigma;
+        (-0.5 * t * t).exp()
+    };
+    Color::from(
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7)
+            - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    )
+}
+
+/// Converts CIE XYZ to linear sRGB (D65 white point).
+pub fn xyz_to_linear_srgb(xyz: &Color) -> Color {
+    Color::from(
+        3.2404542 * xyz.x() - 1.5371385 * xyz.y() - 0.4985314 * xyz.z(),
+        -0.9692660 * xyz.x() + 1.8760108 * xyz.y() + 0.0415560 * xyz.z(),
+        0.0556434 * xyz.x() - 0.2040259 * xyz.y() + 1.0572252 * xyz.z(),
+    )
+}
+
+/// Linear sRGB of a flat spectrum of value one, used to white balance spectral output.
+fn equal_energy_white() -> Color {
+    static WHITE: OnceLock<Color> = OnceLock::new();
+    *WHITE.get_or_init(|| {
+        let steps = 4000;
+        let step = (LAMBDA_MAX - LAMBDA_MIN) / steps as f64;
+        let mut xyz = Color::new();
+        for i in 0..steps {
+            xyz += color_matching(LAMBDA_MIN + (i as f64 + 0.5) * step) * step;
+        }
+        xyz_to_linear_srgb(&xyz)
+    })
+}
@@ -4,7 +4,7 @@ use std::ops::{
     Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
 };
 
-use crate::math::rt_math::{random_number, random_number_in_range};
+use crate::math::rt_math::{random_number, random_number_in_range, random_pair};
 
 pub type Point3 = Vec3;
 
@@ -85,6 +85,20 @@ impl Vec3 {
         self.x * self.x + self.y * self.y + self.z * self.z
     }
 
+    /// Returns the largest of the three coordinates.
+    pub fn max_component(&self) -> f64 {
+        self.x.max(self.y).max(self.z)
+    }
+
+    /// Clamps each coordinate to the range `[min, max]`.
+    pub fn clamp_components(&self, min: f64, max: f64) -> Vec3 {
+        Vec3 {
+            x: self.x.clamp(min, max),
+            y: self.y.clamp(min, max),
+            z: self.z.clamp(min, max),
+        }
+    }
+
     /// Calculates the dot product of two vectors.
     ///
     /// # Arguments
@@ -131,32 +145,26 @@ impl Vec3 {
 
     /// Generates a random vector within a unit sphere.
     ///
-    /// Repeatedly generates a random vector within the range [-1.0, 1.0] for each
-    /// coordinate until it finds one that lies inside the unit sphere (magnitude < 1).
+    /// Maps a pair of random numbers to a height and an angle around the `z` axis, which
+    /// covers the sphere uniformly. Drawing exactly two numbers, unlike rejection sampling,
+    /// keeps each sample's random numbers in step for the camera's sampler.
     ///
     /// # Returns
     ///
     /// A `Vec3` that represents a random normalized vector within the unit sphere.
     pub fn random_unit_sphere_vector() -> Vec3 {
-        loop {
-            let vector: Vec3 = Vec3::random_in_range(-1.0, 1.0);
-            if vector.magnitude() < 1.0 {
-                return vector.normalize();
-            }
-        }
+        let (u, v) = random_pair();
+        let z = 1.0 - 2.0 * u;
+        let radius = f64::sqrt(f64::max(0.0, 1.0 - z * z));
+        let phi = 2.0 * std::f64::consts::PI * v;
+        Vec3::from(radius * phi.cos(), radius * phi.sin(), z)
     }
     /// Generates a random point inside the unit disk
     pub fn random_unit_disk_point() -> Vec3 {
-        loop {
-            let point: Point3 = Point3::from(
-                random_number_in_range(-1.0, 1.0),
-                random_number_in_range(-1.0, 1.0),
-                0.0,
-            );
-            if point.magnitude() < 1.0 {
-                return point;
-            }
-        }
+        let (u, v) = random_pair();
+        let radius = u.sqrt();
+        let phi = 2.0 * std::f64::consts::PI * v;
+        Point3::from(radius * phi.cos(), radius * phi.sin(), 0.0)
     }
 
     /// Generates a random vector that lies on the hemisphere surface defined by the given normal.
@@ -185,6 +193,20 @@ impl Vec3 {
         }
     }
 
+    /// Generates a random direction about the `z` axis with a cosine-weighted distribution.
+    ///
+    /// This is the ideal importance sampling for diffuse surfaces whose normal is `+z`;
+    /// the probability density of the returned direction is `z / π`.
+    pub fn random_cosine_direction() -> Vec3 {
+        let (r1, r2) = random_pair();
+        let phi = 2.0 * std::f64::consts::PI * r1;
+        Vec3::from(
+            phi.cos() * r2.sqrt(),
+            phi.sin() * r2.sqrt(),
+            (1.0 - r2).sqrt(),
+        )
+    }
+
     pub fn random() -> Vec3 {
         return Vec3::from(random_number(), random_number(), random_number());
     }